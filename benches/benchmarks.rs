@@ -1,25 +1,6 @@
-use std::{
-    cmp::Ordering,
-    collections::{BTreeSet, BinaryHeap, HashMap},
-    hint::black_box,
-    iter::{once, repeat_n},
-};
-
-use ahash::AHashMap;
-use anyhow::Result;
-use aoc2024::{Solution, day3::Day3, day4::Day4, day5::Day5, day6::Day6, day9::Day9, grid::Grid};
+use std::hint::black_box;
+
 use divan::Bencher;
-use itertools::{Itertools, izip};
-use nom::{
-    IResult,
-    branch::alt,
-    bytes::complete::{tag, take_until, take_while1},
-    character::complete::char,
-    combinator::{map, opt, rest},
-    multi::fold_many1,
-    sequence::{preceded, terminated, tuple},
-};
-use rand::Rng;
 use regex::Regex;
 
 fn main() {
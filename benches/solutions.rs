@@ -0,0 +1,88 @@
+//! One benchmark per day, part and input, generated from `get_solution`.
+//!
+//! Parsing and solving are measured separately: `parse` times
+//! `TrySolution::parse`, while `part1`/`part2` start from an already parsed
+//! input. Each case is named `{day}/{input}`, so
+//! `cargo bench --bench solutions -- part2` runs every part 2 and divan's
+//! usual filters narrow it down from there.
+
+use std::{any::Any, hint::black_box};
+
+use aoc2024::{
+    DynSolution, Part, get_solution,
+    input::{EnvInputProvider, InputProvider},
+    runner::DAYS,
+};
+use divan::Bencher;
+
+fn main() {
+    divan::main();
+}
+
+#[derive(Debug)]
+struct Case {
+    day: u8,
+    input: String,
+}
+
+impl std::fmt::Display for Case {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}/{}", self.day, self.input)
+    }
+}
+
+impl Case {
+    fn load(&self) -> (Box<dyn DynSolution>, String) {
+        let input = EnvInputProvider::new().get(self.day, &self.input).unwrap();
+
+        (get_solution(self.day), input.trim().to_string())
+    }
+
+    fn parsed(&self) -> (Box<dyn DynSolution>, Box<dyn Any>) {
+        let (solution, input) = self.load();
+        let parsed = solution.parse_any(&input).unwrap();
+
+        (solution, parsed)
+    }
+}
+
+/// Every input that exists and parses, for every registered day.
+fn cases() -> Vec<Case> {
+    let provider = EnvInputProvider::new();
+
+    DAYS.flat_map(|day| {
+        provider
+            .names(day)
+            .into_iter()
+            .map(move |input| Case { day, input })
+    })
+    .filter(|case| {
+        provider
+            .get(case.day, &case.input)
+            .is_ok_and(|input| get_solution(case.day).parse_any(input.trim()).is_ok())
+    })
+    .collect()
+}
+
+fn bench_part(bencher: Bencher, case: &Case, part: Part) {
+    let (mut solution, parsed) = case.parsed();
+
+    bencher.bench_local(move || black_box(solution.run_parsed(part, black_box(parsed.as_ref()))));
+}
+
+#[divan::bench(args = cases())]
+fn parse(bencher: Bencher, case: &Case) {
+    let (solution, input) = case.load();
+
+    bencher.bench_local(move || black_box(solution.parse_any(black_box(&input))));
+}
+
+#[divan::bench(args = cases())]
+fn part1(bencher: Bencher, case: &Case) {
+    bench_part(bencher, case, Part::One);
+}
+
+#[divan::bench(args = cases())]
+fn part2(bencher: Bencher, case: &Case) {
+    bench_part(bencher, case, Part::Two);
+}
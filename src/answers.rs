@@ -0,0 +1,214 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::Part;
+
+/// File the [`AnswerRegistry`] is read from, next to the inputs it describes.
+pub const ANSWERS_FILE: &str = "answers.toml";
+
+/// Known answers keyed by day, input name and part.
+///
+/// Stored as TOML with one table per day and input, which can also carry
+/// the [`params`](mod@crate::params) that input needs:
+///
+/// ```toml
+/// [1.input]
+/// part1 = "1830467"
+/// part2 = "26674158"
+///
+/// [1.example]
+/// part1 = "11"
+///
+/// [14.example]
+/// part1 = "12"
+/// params = { wide = 11, tall = 7 }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AnswerRegistry {
+    answers: BTreeMap<(u8, String, Part), String>,
+    params: BTreeMap<(u8, String), Vec<(String, String)>>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputAnswers {
+    part1: Option<String>,
+    part2: Option<String>,
+    #[serde(default)]
+    params: BTreeMap<String, toml::Value>,
+}
+
+impl AnswerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(contents: &str) -> Result<Self, AnswerError> {
+        let days: BTreeMap<String, BTreeMap<String, InputAnswers>> = toml::from_str(contents)
+            .map_err(|err| AnswerError::Parse {
+                path: None,
+                message: err.to_string(),
+            })?;
+
+        let mut registry = Self::new();
+
+        for (day, inputs) in days {
+            let day = day
+                .parse::<u8>()
+                .map_err(|_| AnswerError::InvalidDay(day.clone()))?;
+
+            for (name, answers) in inputs {
+                for (part, answer) in [(Part::One, answers.part1), (Part::Two, answers.part2)] {
+                    if let Some(answer) = answer {
+                        registry.insert(day, &name, part, answer);
+                    }
+                }
+
+                for (param, value) in answers.params {
+                    let value = match value {
+                        toml::Value::String(s) => s,
+                        value => value.to_string(),
+                    };
+                    registry.insert_param(day, &name, &param, value);
+                }
+            }
+        }
+
+        Ok(registry)
+    }
+
+    /// Loads `path`, treating a missing file as an empty registry.
+    pub fn load(path: &Path) -> Result<Self, AnswerError> {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => Self::parse(&contents).map_err(|err| match err {
+                AnswerError::Parse { message, .. } => AnswerError::Parse {
+                    path: Some(path.to_path_buf()),
+                    message,
+                },
+                err => err,
+            }),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(source) => Err(AnswerError::Io {
+                path: path.to_path_buf(),
+                source,
+            }),
+        }
+    }
+
+    pub fn insert(&mut self, day: u8, name: &str, part: Part, answer: impl Into<String>) {
+        self.answers
+            .insert((day, name.to_string(), part), answer.into());
+    }
+
+    pub fn get(&self, day: u8, name: &str, part: Part) -> Option<&str> {
+        self.answers
+            .get(&(day, name.to_string(), part))
+            .map(String::as_str)
+    }
+
+    pub fn insert_param(&mut self, day: u8, name: &str, param: &str, value: impl Into<String>) {
+        self.params
+            .entry((day, name.to_string()))
+            .or_default()
+            .push((param.to_string(), value.into()));
+    }
+
+    /// Parameters solving input `name` of `day` takes, as `(name, value)`.
+    pub fn params(&self, day: u8, name: &str) -> &[(String, String)] {
+        self.params
+            .get(&(day, name.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+#[derive(Debug)]
+pub enum AnswerError {
+    Parse {
+        path: Option<PathBuf>,
+        message: String,
+    },
+    InvalidDay(String),
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl std::fmt::Display for AnswerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnswerError::Parse {
+                path: Some(path),
+                message,
+            } => write!(f, "parsing {}: {message}", path.display()),
+            AnswerError::Parse {
+                path: None,
+                message,
+            } => write!(f, "parsing answers: {message}"),
+            AnswerError::InvalidDay(day) => write!(f, "{day:?} is not a day"),
+            AnswerError::Io { path, source } => write!(f, "reading {}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for AnswerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AnswerError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let registry = AnswerRegistry::parse(
+            r#"
+[1.input]
+part1 = "1830467"
+part2 = "26674158"
+
+[1.example]
+part1 = "11"
+
+[14.example]
+part1 = "12"
+params = { wide = 11, tall = 7, scorer = "entropy" }
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(registry.get(1, "input", Part::Two), Some("26674158"));
+        assert_eq!(registry.get(1, "example", Part::One), Some("11"));
+        assert_eq!(registry.get(1, "example", Part::Two), None);
+        assert_eq!(registry.get(2, "input", Part::One), None);
+
+        let pair = |name: &str, value: &str| (name.to_string(), value.to_string());
+        assert_eq!(
+            registry.params(14, "example"),
+            [
+                pair("scorer", "entropy"),
+                pair("tall", "7"),
+                pair("wide", "11")
+            ]
+        );
+        assert_eq!(registry.params(1, "example"), []);
+
+        assert!(matches!(
+            AnswerRegistry::parse("[one.input]\npart1 = \"1\""),
+            Err(AnswerError::InvalidDay(_))
+        ));
+        assert!(matches!(
+            AnswerRegistry::parse("[1.input]\npart3 = \"1\""),
+            Err(AnswerError::Parse { .. })
+        ));
+    }
+}
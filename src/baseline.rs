@@ -0,0 +1,242 @@
+use std::{path::Path, time::Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Part, SolveError, get_solution, input::InputProvider};
+
+/// What a timing was taken of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl From<Part> for Stage {
+    fn from(part: Part) -> Self {
+        match part {
+            Part::One => Stage::Part1,
+            Part::Two => Stage::Part2,
+        }
+    }
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part1 => write!(f, "part 1"),
+            Stage::Part2 => write!(f, "part 2"),
+        }
+    }
+}
+
+/// Summary of `runs` timings of one stage of one day.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Timing {
+    pub day: u8,
+    pub input: String,
+    pub stage: Stage,
+    pub runs: usize,
+    pub min_ns: u64,
+    pub median_ns: u64,
+    pub p90_ns: u64,
+    pub p99_ns: u64,
+}
+
+impl Timing {
+    fn from_samples(day: u8, input: &str, stage: Stage, mut samples: Vec<u64>) -> Self {
+        samples.sort_unstable();
+
+        // nearest rank
+        let percentile = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+
+        Self {
+            day,
+            input: input.to_string(),
+            stage,
+            runs: samples.len(),
+            min_ns: samples[0],
+            median_ns: percentile(50),
+            p90_ns: percentile(90),
+            p99_ns: percentile(99),
+        }
+    }
+
+    fn key(&self) -> (u8, &str, Stage) {
+        (self.day, &self.input, self.stage)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub timings: Vec<Timing>,
+}
+
+impl Baseline {
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let contents = std::fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(std::io::Error::other)
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let contents = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(path, contents + "\n")
+    }
+
+    /// Merges `other` in, replacing timings of the same day, input and stage.
+    pub fn merge(&mut self, other: Baseline) {
+        self.timings
+            .retain(|timing| !other.timings.iter().any(|new| new.key() == timing.key()));
+        self.timings.extend(other.timings);
+        self.timings.sort_by(|a, b| a.key().cmp(&b.key()));
+    }
+
+    /// Compares `current` against this baseline. A stage regressed when its
+    /// median got more than `threshold` percent slower.
+    pub fn compare(&self, current: &Baseline, threshold: f64) -> Vec<Comparison> {
+        current
+            .timings
+            .iter()
+            .map(|timing| {
+                let baseline_ns = self
+                    .timings
+                    .iter()
+                    .find(|old| old.key() == timing.key())
+                    .map(|old| old.median_ns);
+
+                let change = baseline_ns.map(|baseline_ns| {
+                    (timing.median_ns as f64 - baseline_ns as f64) / baseline_ns.max(1) as f64
+                        * 100.0
+                });
+
+                Comparison {
+                    timing: timing.clone(),
+                    baseline_ns,
+                    change,
+                    regressed: change.is_some_and(|change| change > threshold),
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    pub timing: Timing,
+    /// Median of the same stage in the baseline, if it was measured there.
+    pub baseline_ns: Option<u64>,
+    /// Change of the median in percent; positive is slower.
+    pub change: Option<f64>,
+    pub regressed: bool,
+}
+
+/// Parses and solves every part of `day` `runs` times, with `params` set.
+pub fn measure_day(
+    provider: &impl InputProvider,
+    day: u8,
+    input_name: &str,
+    parts: &[Part],
+    params: &[(String, String)],
+    runs: usize,
+) -> Result<Vec<Timing>, SolveError> {
+    let runs = runs.max(1);
+    let input = provider.get(day, input_name)?;
+    let input = input.trim();
+    let mut solution = get_solution(day);
+    if !params.is_empty() {
+        solution
+            .configure(params)
+            .map_err(|err| err.with_day(day))?;
+    }
+
+    let mut samples = vec![];
+    let mut parsed = None;
+    for _ in 0..runs {
+        let start = Instant::now();
+        let result = solution.parse_any(input);
+        samples.push(start.elapsed().as_nanos() as u64);
+        parsed = Some(result.map_err(|err| err.with_day(day))?);
+    }
+    let parsed = parsed.unwrap();
+
+    let mut timings = vec![Timing::from_samples(day, input_name, Stage::Parse, samples)];
+
+    for &part in parts {
+        let mut samples = vec![];
+        for _ in 0..runs {
+            let start = Instant::now();
+            let result = solution.run_parsed(part, parsed.as_ref());
+            samples.push(start.elapsed().as_nanos() as u64);
+            result.map_err(|err| err.with_day(day))?;
+        }
+
+        timings.push(Timing::from_samples(day, input_name, part.into(), samples));
+    }
+
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::MemoryInputProvider;
+
+    fn timing(day: u8, stage: Stage, median_ns: u64) -> Timing {
+        Timing::from_samples(day, "input", stage, vec![median_ns])
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let timing = Timing::from_samples(1, "input", Stage::Parse, (1..=100).rev().collect());
+
+        assert_eq!(timing.runs, 100);
+        assert_eq!(timing.min_ns, 1);
+        assert_eq!(timing.median_ns, 50);
+        assert_eq!(timing.p90_ns, 90);
+        assert_eq!(timing.p99_ns, 99);
+    }
+
+    #[test]
+    fn test_compare() {
+        let mut baseline = Baseline {
+            timings: vec![timing(1, Stage::Part1, 100), timing(1, Stage::Part2, 100)],
+        };
+        let current = Baseline {
+            timings: vec![
+                timing(1, Stage::Part1, 105),
+                timing(1, Stage::Part2, 150),
+                timing(2, Stage::Part1, 100),
+            ],
+        };
+
+        let comparisons = baseline.compare(&current, 10.0);
+        assert_eq!(
+            comparisons.iter().map(|c| c.regressed).collect::<Vec<_>>(),
+            vec![false, true, false]
+        );
+        assert_eq!(comparisons[1].change, Some(50.0));
+        assert_eq!(comparisons[2].baseline_ns, None);
+
+        baseline.merge(current);
+        assert_eq!(baseline.timings.len(), 3);
+        assert_eq!(baseline.timings[1].median_ns, 150);
+    }
+
+    #[test]
+    fn test_measure_day() {
+        let provider = MemoryInputProvider::new().with(1, "example", "3   4\n4   3");
+        let timings = measure_day(&provider, 1, "example", Part::all(), &[], 3).unwrap();
+
+        assert_eq!(
+            timings.iter().map(|t| t.stage).collect::<Vec<_>>(),
+            vec![Stage::Parse, Stage::Part1, Stage::Part2]
+        );
+        assert!(timings.iter().all(|t| t.runs == 3));
+        assert!(measure_day(&provider, 2, "example", Part::all(), &[], 3).is_err());
+
+        let params = [("wide".to_string(), "11".to_string())];
+        assert!(measure_day(&provider, 1, "example", Part::all(), &params, 3).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Cycle detection for deterministic simulations.
+//!
+//! A simulation here is an initial state and a `step` function; once a state
+//! repeats, everything after it repeats too. [`floyd`] and [`brent`] find the
+//! cycle in constant memory, [`detect`] hashes every state it sees and keeps
+//! them around so any later step can be looked up without simulating again.
+
+use std::hash::Hash;
+
+use ahash::AHashMap;
+
+/// Where a sequence of states starts repeating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle<S> {
+    /// First step that is part of the cycle.
+    pub start: usize,
+    pub length: usize,
+    /// The state at step `start`.
+    pub entry: S,
+}
+
+impl<S> Cycle<S> {
+    /// The earliest step with the same state as step `n`.
+    pub fn reduce(&self, n: u64) -> usize {
+        let start = self.start as u64;
+        if n < start {
+            return n as usize;
+        }
+
+        self.start + ((n - start) % self.length as u64) as usize
+    }
+}
+
+/// Floyd's tortoise and hare.
+pub fn floyd<S: Clone + Eq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle<S> {
+    let mut tortoise = step(&initial);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        hare = step(&hare);
+    }
+
+    let mut start = 0;
+    tortoise = initial;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    let mut length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        entry: tortoise,
+    }
+}
+
+/// Brent's algorithm; usually fewer steps than [`floyd`].
+pub fn brent<S: Clone + Eq>(initial: S, mut step: impl FnMut(&S) -> S) -> Cycle<S> {
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = initial.clone();
+    let mut hare = step(&initial);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+
+    let mut tortoise = initial.clone();
+    let mut hare = initial;
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+
+    let mut start = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        start += 1;
+    }
+
+    Cycle {
+        start,
+        length,
+        entry: tortoise,
+    }
+}
+
+/// Every state of a simulation up to the point it repeats.
+#[derive(Debug, Clone)]
+pub struct History<S> {
+    pub cycle: Cycle<S>,
+    /// States `0..cycle.start + cycle.length`.
+    pub states: Vec<S>,
+}
+
+impl<S> History<S> {
+    /// The state after `n` steps, however large `n` is.
+    pub fn state_at(&self, n: u64) -> &S {
+        &self.states[self.cycle.reduce(n)]
+    }
+}
+
+/// Finds the cycle by remembering every state, which takes memory but only
+/// simulates each step once.
+pub fn detect<S: Clone + Eq + Hash>(initial: S, mut step: impl FnMut(&S) -> S) -> History<S> {
+    let mut seen = AHashMap::new();
+    let mut states = vec![];
+    let mut state = initial;
+
+    loop {
+        if let Some(&start) = seen.get(&state) {
+            return History {
+                cycle: Cycle {
+                    start,
+                    length: states.len() - start,
+                    entry: state,
+                },
+                states,
+            };
+        }
+
+        seen.insert(state.clone(), states.len());
+        let next = step(&state);
+        states.push(state);
+        state = next;
+    }
+}
+
+/// The state after `n` steps, simulating at most until the first repeat
+/// and then only what is left of one lap.
+pub fn state_at<S: Clone + Eq>(initial: S, mut step: impl FnMut(&S) -> S, n: u64) -> S {
+    let cycle = brent(initial.clone(), &mut step);
+
+    let mut state = initial;
+    for _ in 0..cycle.reduce(n) {
+        state = step(&state);
+    }
+
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 3, 10, 5, 16, 8, 4, 2, 1, 4, 2, 1, ...
+    fn collatz(n: &u64) -> u64 {
+        if n % 2 == 0 { n / 2 } else { 3 * n + 1 }
+    }
+
+    #[test]
+    fn test_algorithms_agree() {
+        let expected = Cycle {
+            start: 5,
+            length: 3,
+            entry: 4,
+        };
+
+        assert_eq!(floyd(3, collatz), expected);
+        assert_eq!(brent(3, collatz), expected);
+        assert_eq!(detect(3, collatz).cycle, expected);
+    }
+
+    #[test]
+    fn test_state_at() {
+        let history = detect(3, collatz);
+
+        assert_eq!(*history.state_at(1), 10);
+        assert_eq!(*history.state_at(7), 1);
+        assert_eq!(*history.state_at(1_000_000_000_000), 1);
+        assert_eq!(state_at(3, collatz, 1_000_000_000_000), 1);
+
+        // a pure cycle from step 0
+        let history = detect(0u8, |n| (n + 1) % 7);
+        assert_eq!(history.cycle.start, 0);
+        assert_eq!(*history.state_at(10u64.pow(12)), (10u64.pow(12) % 7) as u8);
+    }
+}
@@ -1,65 +1,83 @@
 use ahash::AHashMap;
 
-use crate::Solution;
+use crate::{Answer, SolveError, TrySolution};
 
+#[derive(Default)]
 pub struct Day1 {}
 
-impl Solution for Day1 {
-    fn new() -> Self {
+impl Day1 {
+    pub fn new() -> Self {
         Self {}
     }
+}
+
+fn parse_pairs(input: &str) -> impl Iterator<Item = Result<(u32, u32), SolveError>> + '_ {
+    input.lines().enumerate().map(|(row, line)| {
+        let (l, r) = line.split_once("   ").ok_or_else(|| {
+            SolveError::at(row + 1, 1, "expected two numbers separated by three spaces")
+        })?;
+
+        let parse = |s: &str| {
+            s.parse::<u32>().map_err(|err| {
+                SolveError::at_fragment(input, s, format!("invalid location id {s:?}: {err}"))
+            })
+        };
+
+        Ok((parse(l)?, parse(r)?))
+    })
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let (mut left, mut right) = input
-            .lines()
-            .flat_map(|line| line.split_once("   "))
-            .map(|(l, r)| (l.parse::<u32>().unwrap(), r.parse::<u32>().unwrap()))
-            .unzip::<_, _, Vec<_>, Vec<_>>();
+impl TrySolution for Day1 {
+    type Parsed = Vec<(u32, u32)>;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        parse_pairs(input).collect()
+    }
+
+    fn try_part1(&mut self, pairs: &Self::Parsed) -> Result<Answer, SolveError> {
+        let (mut left, mut right) = pairs.iter().copied().unzip::<_, _, Vec<_>, Vec<_>>();
 
         left.sort();
         right.sort();
 
-        left.iter()
+        Ok(left
+            .iter()
             .copied()
             .zip(right.iter().copied())
             .map(|(l, r)| l.abs_diff(r))
             .sum::<u32>()
-            .to_string()
-    }
-
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("1830467"))
+            .into())
     }
 
-    fn part2(&mut self, input: &str) -> String {
+    fn try_part2(&mut self, pairs: &Self::Parsed) -> Result<Answer, SolveError> {
         let mut left = vec![];
         let mut freq = AHashMap::new();
 
-        for (l, r) in input.lines().flat_map(|line| line.split_once("   ")) {
-            left.push(l.parse::<u32>().unwrap());
-            *freq.entry(r.parse::<u32>().unwrap()).or_insert(0) += 1;
+        for &(l, r) in pairs {
+            left.push(l);
+            *freq.entry(r).or_insert(0) += 1;
         }
 
-        left.iter()
+        Ok(left
+            .iter()
             .map(|l| l * freq.get(l).unwrap_or(&0))
             .sum::<u32>()
-            .to_string()
-    }
-
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("26674158"))
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{InputLocation, Part};
 
     #[test]
     fn test_part1() {
         let mut solution = Day1::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"3   4
 4   3
 2   5
@@ -67,7 +85,7 @@ mod tests {
 3   9
 3   3"#
             ),
-            String::from("11")
+            Ok(Answer::from(11u32))
         );
     }
 
@@ -75,7 +93,8 @@ mod tests {
     fn test_part2() {
         let mut solution = Day1::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"3   4
 4   3
 2   5
@@ -83,7 +102,18 @@ mod tests {
 3   9
 3   3"#
             ),
-            String::from("31")
+            Ok(Answer::from(31u32))
         );
     }
+
+    #[test]
+    fn test_bad_input() {
+        let mut solution = Day1::new();
+
+        let err = solution.solve(Part::One, "3   4\n4   x3").unwrap_err();
+        assert_eq!(err.location, Some(InputLocation { line: 2, column: 5 }));
+
+        let err = solution.solve(Part::Two, "3   4\n4 3").unwrap_err();
+        assert_eq!(err.location, Some(InputLocation { line: 2, column: 1 }));
+    }
 }
@@ -1,54 +1,51 @@
 use ahash::AHashSet;
 
 use crate::{
-    Solution,
-    direction::QuadDirection,
+    Answer, SolveError, TrySolution,
     grid::{Coord, Grid},
 };
 
 pub struct Day10 {}
 
-impl Solution for Day10 {
-    fn new() -> Self {
+impl Day10 {
+    pub fn new() -> Self {
         Self {}
     }
+}
+
+impl Default for Day10 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrySolution for Day10 {
+    type Parsed = Grid<u8>;
+    type Params = ();
 
-    fn part1(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| c);
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        Ok(Grid::try_parse(input, |c| match c {
+            b'0'..=b'9' | b'.' => Ok(c),
+            _ => Err(format!("expected a height, found {:?}", c as char)),
+        })?)
+    }
 
-        grid.iter_with_coords()
+    fn try_part1(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(grid
+            .iter_with_coords()
             .filter(|(_, c)| **c == b'0')
             .map(|(c, _)| {
-                #[derive(Debug, Copy, Clone)]
-                struct Item(Coord, QuadDirection);
-                impl std::hash::Hash for Item {
-                    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                        self.0.hash(state);
-                    }
-                }
-                impl PartialEq for Item {
-                    fn eq(&self, other: &Self) -> bool {
-                        self.0 == other.0
-                    }
-                }
-                impl Eq for Item {}
-
-                let mut current_stack: AHashSet<Item> = AHashSet::new();
-                current_stack.insert(Item(c, QuadDirection::None));
+                // heights only go up, so a trail never walks back on itself
+                let mut current_stack: AHashSet<Coord> = AHashSet::new();
+                current_stack.insert(c);
 
                 for next in b'1'..=b'9' {
-                    let mut next_stack = AHashSet::new();
-                    for Item(coord, direction) in current_stack {
-                        for &dir in direction.get_non_opposite_directions() {
-                            let next_coord = coord + dir;
-                            if let Some(next_value) = grid.get(next_coord) {
-                                if *next_value == next {
-                                    next_stack.insert(Item(next_coord, dir));
-                                }
-                            }
-                        }
-                    }
-                    current_stack = next_stack;
+                    current_stack = current_stack
+                        .into_iter()
+                        .flat_map(|coord| grid.neighbors4(coord))
+                        .filter(|(_, value)| **value == next)
+                        .map(|(coord, _)| coord)
+                        .collect();
 
                     if current_stack.is_empty() {
                         return 0;
@@ -58,34 +55,23 @@ impl Solution for Day10 {
                 current_stack.len()
             })
             .sum::<usize>()
-            .to_string()
+            .into())
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("674"))
-    }
-
-    fn part2(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| c);
-
-        grid.iter_with_coords()
+    fn try_part2(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(grid
+            .iter_with_coords()
             .filter(|(_, c)| **c == b'0')
             .map(|(c, _)| {
-                let mut current_stack: Vec<(Coord, QuadDirection)> = vec![(c, QuadDirection::None)];
+                let mut current_stack: Vec<Coord> = vec![c];
 
                 for next in b'1'..=b'9' {
-                    let mut next_stack = Vec::new();
-                    for (coord, from_direction) in current_stack {
-                        for &to_direction in from_direction.get_non_opposite_directions() {
-                            let next_coord = coord + to_direction;
-                            if let Some(next_value) = grid.get(next_coord) {
-                                if *next_value == next {
-                                    next_stack.push((next_coord, to_direction));
-                                }
-                            }
-                        }
-                    }
-                    current_stack = next_stack;
+                    current_stack = current_stack
+                        .into_iter()
+                        .flat_map(|coord| grid.neighbors4(coord))
+                        .filter(|(_, value)| **value == next)
+                        .map(|(coord, _)| coord)
+                        .collect();
 
                     if current_stack.is_empty() {
                         return 0;
@@ -95,23 +81,21 @@ impl Solution for Day10 {
                 current_stack.len()
             })
             .sum::<usize>()
-            .to_string()
-    }
-
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("1372"))
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day10::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"...0...
 ...1...
 ...2...
@@ -120,11 +104,12 @@ mod tests {
 8.....8
 9.....9"#
             ),
-            String::from("2")
+            Ok(Answer::from(2usize))
         );
 
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"..90..9
 ...1.98
 ...2..7
@@ -133,11 +118,12 @@ mod tests {
 876....
 987...."#
             ),
-            String::from("4")
+            Ok(Answer::from(4usize))
         );
 
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"89010123
 78121874
 87430965
@@ -147,7 +133,7 @@ mod tests {
 01329801
 10456732"#
             ),
-            String::from("36")
+            Ok(Answer::from(36usize))
         );
     }
 
@@ -155,7 +141,8 @@ mod tests {
     fn test_part2() {
         let mut solution = Day10::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#".....0.
 ..4321.
 ..5..2.
@@ -164,11 +151,12 @@ mod tests {
 ..8765.
 ..9...."#
             ),
-            String::from("3")
+            Ok(Answer::from(3usize))
         );
 
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"..90..9
 ...1.98
 ...2..7
@@ -177,11 +165,12 @@ mod tests {
 876....
 987...."#
             ),
-            String::from("13")
+            Ok(Answer::from(13usize))
         );
 
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"012345
 123456
 234567
@@ -189,11 +178,12 @@ mod tests {
 4.6789
 56789."#
             ),
-            String::from("227")
+            Ok(Answer::from(227usize))
         );
 
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"89010123
 78121874
 87430965
@@ -203,7 +193,19 @@ mod tests {
 01329801
 10456732"#
             ),
-            String::from("81")
+            Ok(Answer::from(81usize))
         );
     }
+
+    #[test]
+    fn test_bad_input() {
+        let err = Day10::parse("012\n3x4\n").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(crate::InputLocation { line: 2, column: 2 })
+        );
+        assert_eq!(err.message, "expected a height, found 'x'");
+
+        assert!(Day10::parse("012\r\n3.4\r\n").is_ok());
+    }
 }
@@ -1,98 +1,289 @@
 use ahash::AHashMap;
-use itertools::Itertools;
 
-use crate::Solution;
+use crate::{Answer, BigUint, SolveError, TrySolution};
 
-pub struct Day11 {}
-
-fn split_number(num: u64) -> Option<(u64, u64)> {
-    let digits = num.ilog10() + 1;
-    if digits % 2 == 1 {
-        return None;
+crate::params! {
+    pub struct Day11Params {
+        part1_blinks: usize = 25,
+        part2_blinks: usize = 75,
     }
-    let half = digits / 2;
-    let divisor = 10u32.pow(half);
+}
 
-    let first_part = num / divisor as u64;
-    let second_part = num % divisor as u64;
+pub struct Day11 {
+    params: Day11Params,
+}
 
-    Some((first_part, second_part))
+impl Day11 {
+    pub fn new() -> Self {
+        Self {
+            params: Day11Params::default(),
+        }
+    }
 }
 
-pub fn do_stone(stone: u64, depth: u16, saved_stones: &mut AHashMap<(u64, u16), u64>) -> u64 {
-    if depth == 0 {
-        return 1;
+impl Default for Day11 {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    if let Some(result) = saved_stones.get(&(stone, depth)) {
-        return *result;
+/// Engraved number on a stone. Every value a stone can reach from a `u64`
+/// fits, as at most two `* 2024` happen in a row before the digit count comes
+/// out even and the stone splits.
+pub type Stone = u128;
+
+fn split_number(num: Stone) -> Option<(Stone, Stone)> {
+    let digits = num.checked_ilog10().unwrap_or(0) + 1;
+    if digits % 2 == 1 {
+        return None;
     }
+    let divisor = (10 as Stone).pow(digits / 2);
+
+    Some((num / divisor, num % divisor))
+}
+
+/// What a stone turns into when blinked at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Blink {
+    One(Stone),
+    Two(Stone, Stone),
+}
 
-    let sum = {
+impl Blink {
+    fn of(stone: Stone) -> Self {
         if stone == 0 {
-            do_stone(1, depth - 1, saved_stones)
+            Blink::One(1)
         } else if let Some((left, right)) = split_number(stone) {
-            do_stone(left, depth - 1, saved_stones) + do_stone(right, depth - 1, saved_stones)
+            Blink::Two(left, right)
         } else {
-            do_stone(stone * 2024, depth - 1, saved_stones)
+            Blink::One(stone * 2024)
         }
-    };
+    }
+}
+
+/// The row of stones kept as how many stones carry each value, since their
+/// order never matters and a few thousand values cover any number of blinks.
+#[derive(Debug, Clone)]
+pub struct StoneSimulator {
+    stones: AHashMap<Stone, BigUint>,
+    count: BigUint,
+    /// Every value blinked at so far and what it turned into.
+    blinks: AHashMap<Stone, Blink>,
+    step: usize,
+}
 
-    saved_stones.insert((stone, depth), sum);
-    sum
+/// The stone count no longer fits in a [`BigUint`], somewhere after 1,600
+/// blinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CountOverflow {
+    pub step: usize,
 }
 
-impl Solution for Day11 {
-    fn new() -> Self {
-        Self {}
+impl std::fmt::Display for CountOverflow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "stone count overflowed at blink {}", self.step)
     }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let c = input
-            .split(" ")
-            .map(|s| s.parse::<u64>().unwrap())
-            .collect_vec();
+impl std::error::Error for CountOverflow {}
 
-        let mut suma = 0;
+impl From<CountOverflow> for SolveError {
+    fn from(err: CountOverflow) -> Self {
+        SolveError::new(err.to_string())
+    }
+}
+
+/// Stone count and distinct values after some number of blinks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Growth {
+    pub step: usize,
+    pub count: BigUint,
+    pub distinct: usize,
+}
 
-        for i in c {
-            suma += do_stone(i, 25, &mut AHashMap::new());
+impl StoneSimulator {
+    pub fn new(stones: &[u64]) -> Self {
+        let mut counts = AHashMap::new();
+        for &stone in stones {
+            *counts.entry(Stone::from(stone)).or_insert(BigUint::ZERO) += BigUint::ONE;
         }
 
-        suma.to_string()
+        Self {
+            stones: counts,
+            count: BigUint::from(stones.len() as u64),
+            blinks: AHashMap::new(),
+            step: 0,
+        }
+    }
+
+    /// Blinks done so far.
+    pub fn step(&self) -> usize {
+        self.step
+    }
+
+    pub fn count(&self) -> BigUint {
+        self.count
+    }
+
+    /// How many different values are engraved on the stones.
+    pub fn distinct(&self) -> usize {
+        self.stones.len()
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("175006"))
+    pub fn growth(&self) -> Growth {
+        Growth {
+            step: self.step,
+            count: self.count(),
+            distinct: self.distinct(),
+        }
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let c = input
-            .split(" ")
-            .map(|s| s.parse::<u64>().unwrap())
-            .collect_vec();
+    pub fn blink(&mut self) -> Result<(), CountOverflow> {
+        let overflow = CountOverflow {
+            step: self.step + 1,
+        };
+        let mut next = AHashMap::with_capacity(self.stones.len());
+        let mut total = BigUint::ZERO;
+
+        for (&stone, &count) in &self.stones {
+            let mut add = |stone: Stone| -> Result<(), CountOverflow> {
+                let same = next.entry(stone).or_insert(BigUint::ZERO);
+                *same = same.checked_add(count).ok_or(overflow)?;
+                total = total.checked_add(count).ok_or(overflow)?;
+                Ok(())
+            };
+
+            match *self.blinks.entry(stone).or_insert_with(|| Blink::of(stone)) {
+                Blink::One(stone) => add(stone)?,
+                Blink::Two(left, right) => {
+                    add(left)?;
+                    add(right)?;
+                }
+            }
+        }
+
+        self.stones = next;
+        self.count = total;
+        self.step += 1;
 
-        let mut suma = 0;
+        Ok(())
+    }
 
-        for i in c {
-            suma += do_stone(i, 75, &mut AHashMap::new());
+    /// Blinks until `step` blinks are done in total, returning the count.
+    pub fn count_after(&mut self, step: usize) -> Result<BigUint, CountOverflow> {
+        while self.step < step {
+            self.blink()?;
         }
 
-        suma.to_string()
+        Ok(self.count())
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("207961583799296"))
+    /// Growth after every blink from the current one up to `step`.
+    pub fn growth_until(&mut self, step: usize) -> Result<Vec<Growth>, CountOverflow> {
+        let mut curve = vec![self.growth()];
+        while self.step < step {
+            self.blink()?;
+            curve.push(self.growth());
+        }
+
+        Ok(curve)
+    }
+}
+
+impl TrySolution for Day11 {
+    type Parsed = Vec<u64>;
+    type Params = Day11Params;
+
+    fn params(&self) -> Self::Params {
+        self.params.clone()
+    }
+
+    fn set_params(&mut self, params: Self::Params) {
+        self.params = params;
+    }
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        input
+            .split_whitespace()
+            .map(|s| {
+                s.parse()
+                    .map_err(|_| SolveError::at_fragment(input, s, format!("{s:?} is not a stone")))
+            })
+            .collect()
+    }
+
+    fn try_part1(&mut self, stones: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(StoneSimulator::new(stones)
+            .count_after(self.params.part1_blinks)?
+            .into())
+    }
+
+    fn try_part2(&mut self, stones: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(StoneSimulator::new(stones)
+            .count_after(self.params.part2_blinks)?
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{DynSolution, Part};
 
     #[test]
     fn test_shared_sol() {
         let mut solution = Day11::new();
-        assert_eq!(solution.part1(r#"125 17"#), String::from("55312"));
+        assert_eq!(
+            solution
+                .solve(Part::One, r#"125 17"#)
+                .map(|a| a.to_string()),
+            Ok(String::from("55312"))
+        );
+    }
+
+    #[test]
+    fn test_blinks() {
+        let mut solution = Day11::new();
+        solution
+            .configure(&[("part1_blinks".to_string(), "6".to_string())])
+            .unwrap();
+        assert_eq!(
+            solution.solve(Part::One, "125 17"),
+            Ok(Answer::from(BigUint::from(22u8)))
+        );
+
+        assert!(
+            solution
+                .configure(&[("blinks".to_string(), "6".to_string())])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_growth() {
+        let mut simulator = StoneSimulator::new(&[125, 17]);
+        let curve = simulator.growth_until(6).unwrap();
+
+        let counts = curve.iter().map(|g| g.count).collect::<Vec<_>>();
+        let expected = [2u8, 3, 4, 5, 9, 13, 22].map(BigUint::from);
+        assert_eq!(counts, expected);
+        assert_eq!(curve[2].distinct, 4);
+        assert_eq!(simulator.step(), 6);
+
+        // no overflow of the values or counts well past part 2
+        let count = simulator.count_after(500).unwrap();
+        assert!(count > BigUint::from(u64::MAX));
+        assert!(simulator.distinct() < 4000);
+        assert!(matches!(
+            simulator.count_after(5000),
+            Err(CountOverflow { step }) if step > 1600
+        ));
+
+        let mut simulator = StoneSimulator::new(&[u64::MAX / 10]);
+        simulator.blink().unwrap();
+        assert_eq!(
+            simulator.stones.keys().copied().collect::<Vec<_>>(),
+            [Stone::from(u64::MAX / 10) * 2024]
+        );
     }
 }
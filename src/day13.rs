@@ -1,137 +1,170 @@
-use crate::Solution;
-
-use nalgebra::{Matrix2, Vector2};
+use crate::{
+    Answer, Part, SolveError, TrySolution,
+    linear::{Diophantine, cramer},
+};
 
+#[derive(Default)]
 pub struct Day13 {}
 
-fn round_if_close(x: &f64) -> Option<f64> {
-    let rounded = x.round();
-    let distance = (x - rounded).abs();
+impl Day13 {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+const PRESS_LIMIT: i128 = 100;
+const PRIZE_OFFSET: i128 = 10_000_000_000_000;
+const A_COST: i128 = 3;
+const B_COST: i128 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Machine {
+    a: (i64, i64),
+    b: (i64, i64),
+    prize: (i64, i64),
+}
 
-    if distance < 0.001 {
-        Some(rounded)
-    } else {
-        None
+/// How often to press each button to win a machine's prize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Presses {
+    pub a: i128,
+    pub b: i128,
+}
+
+impl Presses {
+    pub fn tokens(&self) -> i128 {
+        A_COST * self.a + B_COST * self.b
     }
 }
 
-impl Solution for Day13 {
-    fn new() -> Self {
-        Self {}
+impl Machine {
+    /// The cheapest way to the prize as `part` has it: at most 100 presses
+    /// per button in part 1, the prize 10^13 further away in part 2.
+    pub fn presses(&self, part: Part) -> Option<Presses> {
+        match part {
+            Part::One => self.cheapest(0, Some(PRESS_LIMIT)),
+            Part::Two => self.cheapest(PRIZE_OFFSET, None),
+        }
     }
 
-    fn part1(&mut self, input: &str) -> String {
-        let mut sum = 0;
-        for section in input.split("\n\n") {
-            let mut lines = section.lines();
-
-            let mut split_next = |sep: &str| -> (f64, f64) {
-                lines
-                    .next()
-                    .unwrap()
-                    .split_once(": ")
-                    .unwrap()
-                    .1
-                    .split_once(", ")
-                    .map(|(a, b)| {
-                        (
-                            a.split_once(sep).unwrap().1.parse::<f64>().unwrap(),
-                            b.split_once(sep).unwrap().1.parse::<f64>().unwrap(),
-                        )
-                    })
-                    .unwrap()
-            };
-
-            let (a_x, a_y) = split_next("+");
-            let (b_x, b_y) = split_next("+");
-            let (prize_x, prize_y) = split_next("=");
-
-            let a = Matrix2::new(a_x, b_x, a_y, b_y);
-            let prize = Vector2::new(prize_x, prize_y);
-            let x = a.try_inverse().map(|inv_a| inv_a * prize).unwrap();
-
-            if x.iter()
-                .any(|x| !(0.0..=100.0).contains(x) || (0.001..0.999).contains(&x.fract()))
-            {
-                continue;
-            }
-
-            let a = x[0].round() as usize;
-            let b = x[1].round() as usize;
-
-            sum += a * 3 + b;
+    fn cheapest(&self, offset: i128, limit: Option<i128>) -> Option<Presses> {
+        let (ax, ay) = (i128::from(self.a.0), i128::from(self.a.1));
+        let (bx, by) = (i128::from(self.b.0), i128::from(self.b.1));
+        let (px, py) = (
+            i128::from(self.prize.0) + offset,
+            i128::from(self.prize.1) + offset,
+        );
+
+        let in_range = |n: i128| n >= 0 && limit.is_none_or(|limit| n <= limit);
+
+        if let Some([a, b]) = cramer(&[[ax, bx], [ay, by]], [px, py]) {
+            let (a, b) = (a.to_integer()?, b.to_integer()?);
+            return (in_range(a) && in_range(b)).then_some(Presses { a, b });
         }
 
-        sum.to_string()
-    }
+        // both buttons move along one line, which the prize has to be on too
+        if ax * py != ay * px || bx * py != by * px {
+            return None;
+        }
+
+        let (a, b, p) = if (ax, bx) != (0, 0) {
+            (ax, bx, px)
+        } else {
+            (ay, by, py)
+        };
+        if (a, b) == (0, 0) {
+            // neither button moves the claw at all
+            return (px == 0 && py == 0).then_some(Presses { a: 0, b: 0 });
+        }
+
+        let line = Diophantine::solve(a, b, p)?;
+        let (lo, hi) = line.non_negative(limit)?;
+
+        // the cost changes by the same amount with every step along the line
+        let slope = A_COST * line.dx - B_COST * line.dy;
+        let k = if slope > 0 { lo? } else { hi.or(lo)? };
+        let (a, b) = line.at(k);
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("29388"))
+        Some(Presses { a, b })
     }
+}
 
-    fn part2(&mut self, input: &str) -> String {
-        let mut sum = 0;
-        for section in input.split("\n\n") {
-            let mut lines = section.lines();
-
-            let mut split_next = |sep: &str| -> (f64, f64) {
-                lines
-                    .next()
-                    .unwrap()
-                    .split_once(": ")
-                    .unwrap()
-                    .1
-                    .split_once(", ")
-                    .map(|(a, b)| {
-                        (
-                            a.split_once(sep).unwrap().1.parse::<f64>().unwrap(),
-                            b.split_once(sep).unwrap().1.parse::<f64>().unwrap(),
-                        )
-                    })
-                    .unwrap()
-            };
-
-            let (a_x, a_y) = split_next("+");
-            let (b_x, b_y) = split_next("+");
-            let (mut prize_x, mut prize_y) = split_next("=");
-
-            prize_x += 10000000000000.0;
-            prize_y += 10000000000000.0;
-
-            let a = Matrix2::new(a_x, b_x, a_y, b_y);
-            let prize = Vector2::new(prize_x, prize_y);
-            let x = a.try_inverse().map(|inv_a| inv_a * prize).unwrap();
-
-            if x.iter()
-                .map(round_if_close)
-                .any(|x| !matches!(x, Some(x) if x >= 0.0))
-            {
-                continue;
-            }
-
-            let a = x[0].round() as usize;
-            let b = x[1].round() as usize;
-
-            sum += a * 3 + b;
-        }
+fn total_tokens(machines: &[Machine], part: Part) -> i128 {
+    machines
+        .iter()
+        .filter_map(|machine| machine.presses(part))
+        .map(|presses| presses.tokens())
+        .sum()
+}
+
+fn parse_machine(input: &str, section: &str) -> Result<Machine, SolveError> {
+    let mut lines = section.lines();
+
+    let mut split_next = |label: &str, sep: &str| -> Result<(i64, i64), SolveError> {
+        let line = lines.next().ok_or_else(|| {
+            SolveError::at_fragment(input, &section[section.len()..], format!("missing {label}"))
+        })?;
+        let bad_line = || SolveError::at_fragment(input, line, format!("malformed {label}"));
+
+        let (a, b) = line
+            .strip_prefix(label)
+            .and_then(|rest| rest.strip_prefix(": "))
+            .and_then(|rest| rest.split_once(", "))
+            .ok_or_else(bad_line)?;
+
+        let coordinate = |s: &str| {
+            s.split_once(sep)
+                .and_then(|(_, n)| n.parse::<i64>().ok())
+                .ok_or_else(|| SolveError::at_fragment(input, s, format!("malformed {label}")))
+        };
+
+        Ok((coordinate(a)?, coordinate(b)?))
+    };
+
+    Ok(Machine {
+        a: split_next("Button A", "+")?,
+        b: split_next("Button B", "+")?,
+        prize: split_next("Prize", "=")?,
+    })
+}
+
+impl TrySolution for Day13 {
+    type Parsed = Vec<Machine>;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        let separator = if input.contains("\r\n\r\n") {
+            "\r\n\r\n"
+        } else {
+            "\n\n"
+        };
+
+        input
+            .split(separator)
+            .map(|section| parse_machine(input, section))
+            .collect()
+    }
 
-        sum.to_string()
+    fn try_part1(&mut self, machines: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(total_tokens(machines, Part::One).into())
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("99548032866004"))
+    fn try_part2(&mut self, machines: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(total_tokens(machines, Part::Two).into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::InputLocation;
 
     #[test]
     fn test_part1() {
         let mut solution = Day13::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"Button A: X+94, Y+34
 Button B: X+22, Y+67
 Prize: X=8400, Y=5400
@@ -148,7 +181,7 @@ Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279"#
             ),
-            String::from("480")
+            Ok(Answer::from(480i128))
         );
     }
 
@@ -156,7 +189,8 @@ Prize: X=18641, Y=10279"#
     fn test_part2() {
         let mut solution = Day13::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"Button A: X+94, Y+34
 Button B: X+22, Y+67
 Prize: X=8400, Y=5400
@@ -173,7 +207,85 @@ Button A: X+69, Y+23
 Button B: X+27, Y+71
 Prize: X=18641, Y=10279"#
             ),
-            String::from("875318608908")
+            Ok(Answer::from(875318608908i128))
+        );
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let err = Day13::parse("Button A: X+94, Y+34\nButton B: X+22, Y=67\nPrize: X=8400, Y=5400")
+            .unwrap_err();
+
+        assert_eq!(
+            err.location,
+            Some(InputLocation {
+                line: 2,
+                column: 17
+            })
+        );
+
+        let crlf = "Button A: X+94, Y+34\r\nButton B: X+22, Y+67\r\nPrize: X=8400, Y=5400\r\n\r\n\
+                    Button A: X+26, Y+66\r\nButton B: X+67, Y+21\r\nPrize: X=12748, Y=12176";
+        assert_eq!(
+            Day13::parse(crlf),
+            Ok(vec![
+                machine((94, 34), (22, 67), (8400, 5400)),
+                machine((26, 66), (67, 21), (12748, 12176)),
+            ])
+        );
+    }
+
+    fn machine(a: (i64, i64), b: (i64, i64), prize: (i64, i64)) -> Machine {
+        Machine { a, b, prize }
+    }
+
+    #[test]
+    fn test_presses() {
+        let first = machine((94, 34), (22, 67), (8400, 5400));
+        assert_eq!(first.presses(Part::One), Some(Presses { a: 80, b: 40 }));
+        assert_eq!(first.presses(Part::Two), None);
+
+        let second = machine((26, 66), (67, 21), (12748, 12176));
+        assert_eq!(second.presses(Part::One), None);
+        assert_eq!(
+            second.presses(Part::Two),
+            Some(Presses {
+                a: 118679050709,
+                b: 103199174542
+            })
+        );
+    }
+
+    #[test]
+    fn test_collinear_buttons() {
+        // B is the cheaper way to cover distance
+        let m = machine((4, 2), (2, 1), (20, 10));
+        assert_eq!(m.presses(Part::One), Some(Presses { a: 0, b: 10 }));
+
+        // A is, but B has to make up the remainder
+        let m = machine((8, 4), (2, 1), (10, 5));
+        assert_eq!(m.presses(Part::One), Some(Presses { a: 1, b: 1 }));
+        let m = machine((16, 8), (2, 1), (34, 17));
+        assert_eq!(m.presses(Part::One), Some(Presses { a: 2, b: 1 }));
+
+        // the press limit rules out the cheapest combination
+        let m = machine((2, 2), (1, 1), (300, 300));
+        assert_eq!(m.presses(Part::One), Some(Presses { a: 100, b: 100 }));
+        assert_eq!(
+            m.presses(Part::Two),
+            Some(Presses {
+                a: 0,
+                b: 10_000_000_000_300
+            })
+        );
+
+        // off the line, or only reachable with negative presses
+        assert_eq!(machine((2, 1), (4, 2), (7, 4)).presses(Part::One), None);
+        assert_eq!(machine((4, 2), (6, 3), (2, 1)).presses(Part::One), None);
+        assert_eq!(
+            machine((0, 0), (0, 0), (0, 0)).presses(Part::One),
+            Some(Presses { a: 0, b: 0 })
         );
+        assert_eq!(machine((0, 0), (0, 0), (5, 0)).presses(Part::One), None);
     }
 }
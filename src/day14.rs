@@ -1,172 +1,324 @@
-use itertools::Itertools;
-
 use crate::{
-    Solution,
-    direction::QuadDirection,
+    Answer, SolveError, TrySolution,
     grid::{Coord, Grid},
+    instrument,
 };
 
-pub struct Day14 {
-    wide: i64,
-    tall: i64,
+/// Most frames [`Day14::find_picture`] scores. The real floor repeats every
+/// 10403 steps; one near the largest [`FloorSize`] would take a billion.
+const MAX_PERIOD: i64 = 1_000_000;
+
+/// How [`Day14::find_picture`] recognises the frame with a picture in it.
+///
+/// Every scorer gives lower scores to frames where the robots bunch up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scorer {
+    /// Sum of the variance of the rows and of the columns.
+    #[default]
+    Variance,
+    /// Shannon entropy of the row and column histograms.
+    Entropy,
+    /// The part 1 product of robots per quadrant.
+    SafetyFactor,
+    /// Area of the largest group of touching robots, negated. Much slower
+    /// than the others as it labels the regions of the whole floor every step.
+    LargestRegion,
 }
 
-impl Day14 {
-    fn new_with_size(wide: i64, tall: i64) -> Self {
-        Self { wide, tall }
+impl std::str::FromStr for Scorer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "variance" => Ok(Self::Variance),
+            "entropy" => Ok(Self::Entropy),
+            "safety_factor" => Ok(Self::SafetyFactor),
+            "largest_region" => Ok(Self::LargestRegion),
+            _ => Err("expected variance, entropy, safety_factor or largest_region".to_string()),
+        }
     }
 }
 
-impl Solution for Day14 {
-    fn new() -> Self {
-        Self {
-            wide: 101,
-            tall: 103,
-        }
+/// One side of the floor, at most `i16::MAX` tiles so that it can be drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloorSize(i64);
+
+impl FloorSize {
+    pub fn get(self) -> i64 {
+        self.0
     }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let mut top_left: usize = 0;
-        let mut top_right: usize = 0;
-        let mut bottom_left: usize = 0;
-        let mut bottom_right: usize = 0;
-
-        for line in input.lines() {
-            let (p, v) = line.split_once(" ").unwrap();
-            let p = p.split_once("=").unwrap().1;
-            let v = v.split_once("=").unwrap().1;
-            let (p1, p2) = p.split_once(",").unwrap();
-            let (v1, v2) = v.split_once(",").unwrap();
-            let p1 = p1.parse::<i64>().unwrap();
-            let p2 = p2.parse::<i64>().unwrap();
-            let v1 = v1.parse::<i64>().unwrap();
-            let v2 = v2.parse::<i64>().unwrap();
-
-            let col = (p1 + v1 * 100).rem_euclid(self.wide);
-            let row = (p2 + v2 * 100).rem_euclid(self.tall);
-
-            if col < self.wide / 2 && row < self.tall / 2 {
-                top_left += 1;
-            } else if col > self.wide / 2 && row < self.tall / 2 {
-                top_right += 1;
-            } else if col < self.wide / 2 && row > self.tall / 2 {
-                bottom_left += 1;
-            } else if col > self.wide / 2 && row > self.tall / 2 {
-                bottom_right += 1;
-            }
-        }
+impl TryFrom<i64> for FloorSize {
+    type Error = String;
 
-        (top_left * top_right * bottom_left * bottom_right).to_string()
+    fn try_from(n: i64) -> Result<Self, Self::Error> {
+        if (1..=i64::from(i16::MAX)).contains(&n) {
+            Ok(Self(n))
+        } else {
+            Err(format!("expected 1 to {} tiles", i16::MAX))
+        }
     }
+}
 
-    fn known_solution_part1(&self) -> Option<String> {
-        None
+impl std::str::FromStr for FloorSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse::<i64>().map_err(|err| err.to_string())?.try_into()
     }
+}
 
-    fn part2(&mut self, input: &str) -> String {
-        let mut starting_coords = vec![];
-        let mut velocities = vec![];
+crate::params! {
+    pub struct Day14Params {
+        /// Width of the floor; the example's is 11.
+        wide: FloorSize = FloorSize(101),
+        /// Height of the floor; the example's is 7.
+        tall: FloorSize = FloorSize(103),
+        scorer: Scorer = Scorer::default(),
+        /// Have part 2 draw the frame it found, see [`TrySolution::take_render`].
+        render: bool = false,
+    }
+}
 
-        for line in input.lines() {
-            let (p, v) = line.split_once(" ").unwrap();
-            let p = p.split_once("=").unwrap().1;
-            let v = v.split_once("=").unwrap().1;
-            let (p1, p2) = p.split_once(",").unwrap();
-            let (v1, v2) = v.split_once(",").unwrap();
-            let p1 = p1.parse::<i64>().unwrap();
-            let p2 = p2.parse::<i64>().unwrap();
-            let v1 = v1.parse::<i64>().unwrap();
-            let v2 = v2.parse::<i64>().unwrap();
+pub struct Day14 {
+    params: Day14Params,
+    picture: Option<Vec<String>>,
+}
 
-            starting_coords.push((p1, p2));
-            velocities.push((v1, v2));
+impl Day14 {
+    pub fn new() -> Self {
+        Self {
+            params: Day14Params::default(),
+            picture: None,
         }
+    }
+
+    /// For the example, which happens on an 11 by 7 floor.
+    pub fn new_with_size(wide: i64, tall: i64) -> Result<Self, SolveError> {
+        let size = |name: &str, n: i64| {
+            FloorSize::try_from(n)
+                .map_err(|err| SolveError::new(format!("{n} is not a valid {name}: {err}")))
+        };
+
+        Ok(Self {
+            params: Day14Params {
+                wide: size("wide", wide)?,
+                tall: size("tall", tall)?,
+                ..Day14Params::default()
+            },
+            picture: None,
+        })
+    }
+
+    fn positions_at(&self, robots: &[Robot], step: i64) -> impl Iterator<Item = (i64, i64)> {
+        robots.iter().map(move |robot| {
+            (
+                (robot.position.0 + robot.velocity.0 * step).rem_euclid(self.params.wide.get()),
+                (robot.position.1 + robot.velocity.1 * step).rem_euclid(self.params.tall.get()),
+            )
+        })
+    }
 
-        // wait for user to press enter
-        for i in 1.. {
-            let coords = starting_coords
-                .iter()
-                .zip(velocities.iter())
-                .map(|(&(p1, p2), &(v1, v2))| {
-                    Coord::new(
-                        (p1 + v1 * i).rem_euclid(self.wide) as i16,
-                        (p2 + v2 * i).rem_euclid(self.tall) as i16,
-                    )
-                })
-                .collect_vec();
-
-            #[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-            struct Edge {
-                from: Coord,
-                to: Coord,
+    fn safety_factor(&self, positions: impl Iterator<Item = (i64, i64)>) -> usize {
+        let mut quadrants = [0; 4];
+        let (mid_col, mid_row) = (self.params.wide.get() / 2, self.params.tall.get() / 2);
+
+        for (col, row) in positions {
+            if col == mid_col || row == mid_row {
+                continue;
             }
 
-            impl Edge {
-                fn combine_edges(self, other: Self) -> Option<Self> {
-                    if self.from.is_adjacent(other.to) || self.to.is_adjacent(other.from) {
-                        let (from, to) = [(other.from, self.to), (other.to, self.from)]
-                            .iter()
-                            .copied()
-                            .max_by_key(|&(x, y)| x.manhattan_distance(y))
-                            .unwrap();
+            quadrants[usize::from(col > mid_col) + 2 * usize::from(row > mid_row)] += 1;
+        }
 
-                        return Some(Edge { from, to });
-                    }
+        quadrants.iter().product()
+    }
 
-                    None
+    fn score(&self, robots: &[Robot], step: i64) -> f64 {
+        let positions = self.positions_at(robots, step);
+
+        match self.params.scorer {
+            Scorer::Variance => {
+                let (cols, rows): (Vec<_>, Vec<_>) = positions.unzip();
+                variance(&cols) + variance(&rows)
+            }
+            Scorer::Entropy => {
+                let mut cols = vec![0; self.params.wide.get() as usize];
+                let mut rows = vec![0; self.params.tall.get() as usize];
+                for (col, row) in positions {
+                    cols[col as usize] += 1;
+                    rows[row as usize] += 1;
                 }
+                entropy(&cols) + entropy(&rows)
             }
-
-            let edges: Vec<Edge> = coords
-                .iter()
-                .map(|&coord| Edge {
-                    from: coord,
-                    to: coord,
-                })
-                .fold(vec![], |mut all: Vec<Edge>, edge| {
-                    let mut combined = false;
-                    for saved_edge in all.iter_mut() {
-                        if let Some(new_edge) = saved_edge.combine_edges(edge) {
-                            *saved_edge = new_edge;
-                            combined = true;
-                        }
-                    }
-                    if !combined {
-                        all.push(edge);
-                    }
-
-                    all
-                });
-
-            if !edges
-                .iter()
-                .any(|edge| edge.from.manhattan_distance(edge.to) > 5)
-            {
-                continue;
+            Scorer::SafetyFactor => self.safety_factor(positions) as f64,
+            Scorer::LargestRegion => {
+                let floor = self.floor_at(robots, step);
+                let largest = floor
+                    .label_regions(|a, b| a == b)
+                    .regions
+                    .iter()
+                    .filter(|region| floor[region.cells[0]] == '#')
+                    .map(|region| region.area)
+                    .max()
+                    .unwrap_or(0);
+                -(largest as f64)
             }
+        }
+    }
 
-            let mut grid = Grid::new_blank(self.wide as i16, self.tall as i16, ' ');
-            grid.set_all_coords_to(coords.iter().copied(), '.');
-            grid.pretty_print();
+    /// The step within one full period of the floor, `wide * tall` steps,
+    /// whose frame scores lowest.
+    ///
+    /// Ticks `day14.steps` for every frame, and refuses floors whose period is
+    /// over [`MAX_PERIOD`].
+    pub fn find_picture(&self, robots: &[Robot]) -> Result<i64, SolveError> {
+        let (wide, tall) = (self.params.wide.get(), self.params.tall.get());
+        let period = wide * tall;
+        if period > MAX_PERIOD {
+            return Err(SolveError::new(format!(
+                "a {wide} by {tall} floor repeats every {period} steps, more than the {MAX_PERIOD} searched"
+            )));
+        }
+
+        let mut best = (0, f64::INFINITY);
+        for step in 0..period {
+            instrument::tick("day14.steps")?;
+
+            let score = self.score(robots, step);
+            if score < best.1 {
+                best = (step, score);
+            }
         }
 
-        String::new()
+        Ok(best.0)
+    }
+
+    /// The floor after `step` steps, `#` for every tile with a robot.
+    fn floor_at(&self, robots: &[Robot], step: i64) -> Grid<char> {
+        let mut grid = Grid::new_blank(
+            self.params.wide.get() as i16,
+            self.params.tall.get() as i16,
+            '.',
+        );
+        grid.set_all_coords_to(
+            self.positions_at(robots, step)
+                .map(|(col, row)| Coord::new(row as i16, col as i16)),
+            '#',
+        );
+
+        grid
+    }
+
+    /// Draws the floor after `step` steps, `#` for every tile with a robot.
+    pub fn render(&self, robots: &[Robot], step: i64) -> Vec<String> {
+        self.floor_at(robots, step).pretty_print_into_rows()
+    }
+}
+
+impl Default for Day14 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn variance(values: &[i64]) -> f64 {
+    let n = values.len().max(1) as f64;
+    let mean = values.iter().sum::<i64>() as f64 / n;
+
+    values
+        .iter()
+        .map(|&v| (v as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n
+}
+
+fn entropy(histogram: &[usize]) -> f64 {
+    let total = histogram.iter().sum::<usize>().max(1) as f64;
+
+    histogram
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Robot {
+    position: (i64, i64),
+    velocity: (i64, i64),
+}
+
+fn parse_robot(input: &str, line: &str) -> Result<Robot, SolveError> {
+    let pair = |label: &str, s: &str| -> Result<(i64, i64), SolveError> {
+        let bad = || SolveError::at_fragment(input, s, format!("malformed {label}"));
+
+        let (x, y) = s
+            .strip_prefix(label)
+            .and_then(|rest| rest.strip_prefix('='))
+            .and_then(|rest| rest.split_once(','))
+            .ok_or_else(bad)?;
+
+        Ok((x.parse().map_err(|_| bad())?, y.parse().map_err(|_| bad())?))
+    };
+
+    let (p, v) = line
+        .split_once(' ')
+        .ok_or_else(|| SolveError::at_fragment(input, line, "expected a position and velocity"))?;
+
+    Ok(Robot {
+        position: pair("p", p)?,
+        velocity: pair("v", v)?,
+    })
+}
+
+impl TrySolution for Day14 {
+    type Parsed = Vec<Robot>;
+    type Params = Day14Params;
+
+    fn params(&self) -> Self::Params {
+        self.params.clone()
+    }
+
+    fn set_params(&mut self, params: Self::Params) {
+        self.params = params;
+    }
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        input.lines().map(|line| parse_robot(input, line)).collect()
+    }
+
+    fn try_part1(&mut self, robots: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(self.safety_factor(self.positions_at(robots, 100)).into())
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        None
+    fn try_part2(&mut self, robots: &Self::Parsed) -> Result<Answer, SolveError> {
+        let step = self.find_picture(robots)?;
+        self.picture = self.params.render.then(|| self.render(robots, step));
+
+        Ok(step.into())
+    }
+
+    fn take_render(&mut self) -> Option<Vec<String>> {
+        self.picture.take()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{DynSolution, Part};
 
     #[test]
     fn test_part1() {
-        let mut solution = Day14::new_with_size(11, 7);
+        let mut solution = Day14::new_with_size(11, 7).unwrap();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"p=0,4 v=3,-3
 p=6,3 v=-1,-3
 p=10,3 v=-1,2
@@ -180,7 +332,125 @@ p=7,3 v=-1,2
 p=2,4 v=2,-3
 p=9,5 v=-3,-3"#
             ),
-            String::from("12")
+            Ok(Answer::from(12usize))
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        // every robot ends up in the 3x3 square at the top left after 20 steps
+        let velocities = [
+            (1, 2),
+            (-2, 3),
+            (3, -1),
+            (4, 1),
+            (-1, -3),
+            (2, 2),
+            (5, 3),
+            (-3, 1),
+            (1, -2),
+        ];
+        let robots = velocities
+            .iter()
+            .enumerate()
+            .map(|(i, &(vx, vy))| {
+                let target = ((i % 3) as i64, (i / 3) as i64);
+                Robot {
+                    position: (
+                        (target.0 - vx * 20).rem_euclid(11),
+                        (target.1 - vy * 20).rem_euclid(7),
+                    ),
+                    velocity: (vx, vy),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        for scorer in [Scorer::Variance, Scorer::Entropy, Scorer::LargestRegion] {
+            let mut solution = Day14::new();
+            solution.set_params(Day14Params {
+                wide: FloorSize(11),
+                tall: FloorSize(7),
+                scorer,
+                render: false,
+            });
+            assert_eq!(
+                solution.try_part2(&robots),
+                Ok(Answer::from(20i64)),
+                "{scorer:?}"
+            );
+            assert_eq!(TrySolution::take_render(&mut solution), None);
+        }
+
+        let mut solution = Day14::new_with_size(11, 7).unwrap();
+        solution
+            .configure(&[("render".to_string(), "true".to_string())])
+            .unwrap();
+        assert_eq!(solution.try_part2(&robots), Ok(Answer::from(20i64)));
+        let picture = TrySolution::take_render(&mut solution).unwrap();
+        assert_eq!(
+            &picture[..4],
+            ["###........", "###........", "###........", "..........."]
+        );
+    }
+
+    #[test]
+    fn test_floor_size() {
+        let mut solution = Day14::new();
+        for bad in ["0", "-11", "32768"] {
+            let err = solution
+                .configure(&[("wide".to_string(), bad.to_string())])
+                .unwrap_err();
+            assert_eq!(
+                err.message,
+                format!("{bad:?} is not a valid wide: expected 1 to 32767 tiles")
+            );
+        }
+        assert!(
+            solution
+                .configure(&[("tall".to_string(), "32767".to_string())])
+                .is_ok()
+        );
+        assert_eq!(solution.params.tall.get(), 32767);
+
+        // the whole period of a 101 by 32767 floor is too long to search
+        let robots = Day14::parse("p=0,4 v=3,-3").unwrap();
+        let err = solution.find_picture(&robots).unwrap_err();
+        assert_eq!(
+            err.message,
+            "a 101 by 32767 floor repeats every 3309467 steps, more than the 1000000 searched"
+        );
+
+        assert_eq!(
+            Day14::new_with_size(0, 7).err().unwrap().message,
+            "0 is not a valid wide: expected 1 to 32767 tiles"
+        );
+    }
+
+    #[test]
+    fn test_step_budget() {
+        let robots = Day14::parse("p=0,4 v=3,-3").unwrap();
+        let solution = Day14::new_with_size(11, 7).unwrap();
+
+        let context = instrument::Context::new();
+        assert_eq!(context.enter(|| solution.find_picture(&robots)), Ok(0));
+        assert_eq!(context.count("day14.steps"), 77);
+
+        context.set_budget("day14.steps", 10);
+        let err = context
+            .enter(|| solution.find_picture(&robots))
+            .unwrap_err();
+        assert!(
+            err.message
+                .starts_with("day14.steps went over its budget of 10")
+        );
+    }
+
+    #[test]
+    fn test_bad_input() {
+        let err = Day14::parse("p=0,4 v=3,-3\np=6,3 v=-1").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(crate::InputLocation { line: 2, column: 7 })
         );
     }
 }
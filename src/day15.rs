@@ -2,16 +2,160 @@ use ahash::AHashSet;
 use itertools::Itertools;
 
 use crate::{
-    Solution,
+    Answer, SolveError, TrySolution,
     direction::QuadDirection,
     grid::{Coord, Grid},
-    panic_after,
+    instrument,
+    invariant::Invariants,
+    replay::ReplayFrame,
 };
 
-pub struct Day15 {}
+pub struct Day15 {
+    /// Check [`invariants`] after every move; on by default in debug builds.
+    pub validate: bool,
+}
+
+impl Day15 {
+    pub fn new() -> Self {
+        Self {
+            validate: cfg!(debug_assertions),
+        }
+    }
+}
+
+impl Default for Day15 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+pub struct Warehouse {
+    grid: Grid<MapMember>,
+    moves: Vec<QuadDirection>,
+}
+
+/// Gets told about every step of a warehouse simulation, and may stop it by
+/// returning an error.
+///
+/// The first call is the starting position, with [`QuadDirection::None`] as
+/// its move; every later one follows a move, whether or not the robot could
+/// make it.
+pub trait Recorder {
+    fn record(
+        &mut self,
+        dir: QuadDirection,
+        robot: Coord,
+        grid: &Grid<MapMember>,
+    ) -> Result<(), SolveError>;
+}
+
+/// Records nothing, for when only the answer matters.
+impl Recorder for () {
+    fn record(
+        &mut self,
+        _dir: QuadDirection,
+        _robot: Coord,
+        _grid: &Grid<MapMember>,
+    ) -> Result<(), SolveError> {
+        Ok(())
+    }
+}
+
+/// A snapshot of the warehouse after one move.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub dir: QuadDirection,
+    pub robot: Coord,
+    pub grid: Grid<MapMember>,
+}
+
+impl Recorder for Vec<Frame> {
+    fn record(
+        &mut self,
+        dir: QuadDirection,
+        robot: Coord,
+        grid: &Grid<MapMember>,
+    ) -> Result<(), SolveError> {
+        self.push(Frame {
+            dir,
+            robot,
+            grid: grid.clone(),
+        });
+
+        Ok(())
+    }
+}
+
+impl Recorder for Invariants<MapMember> {
+    fn record(
+        &mut self,
+        dir: QuadDirection,
+        _robot: Coord,
+        grid: &Grid<MapMember>,
+    ) -> Result<(), SolveError> {
+        let action = match dir {
+            QuadDirection::None => "start".to_string(),
+            dir => format!("move {dir}"),
+        };
+
+        Ok(self.check(action, grid)?)
+    }
+}
+
+/// What has to hold after every move in both parts.
+pub fn invariants() -> Invariants<MapMember> {
+    Invariants::new()
+        .with("exactly one robot", |grid| {
+            match grid
+                .iter_lines()
+                .flatten()
+                .filter(|m| **m == MapMember::Robot)
+                .count()
+            {
+                1 => Ok(()),
+                n => Err(format!("found {n} robots")),
+            }
+        })
+        .with("crates stay whole", |grid| {
+            for (row, line) in grid.iter_lines().enumerate() {
+                for (col, m) in line.iter().enumerate() {
+                    let (neighbour, expected) = match m {
+                        MapMember::CrateLeft => (line.get(col + 1), MapMember::CrateRight),
+                        MapMember::CrateRight => (
+                            col.checked_sub(1).and_then(|col| line.get(col)),
+                            MapMember::CrateLeft,
+                        ),
+                        _ => continue,
+                    };
+
+                    if neighbour != Some(&expected) {
+                        let coord = Coord::new_usize(row, col);
+                        return Err(format!("{m} at {coord} is missing its {expected}"));
+                    }
+                }
+            }
+
+            Ok(())
+        })
+}
+
+impl ReplayFrame for Frame {
+    fn title(&self) -> String {
+        match self.dir {
+            QuadDirection::None => format!("start, robot at {}", self.robot),
+            dir => format!("move {dir}, robot at {}", self.robot),
+        }
+    }
+
+    fn rows(&self) -> Vec<String> {
+        self.grid
+            .pretty_print_bolded_coords_into_rows(&[self.robot])
+    }
+}
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
-enum MapMember {
+pub enum MapMember {
     Empty,
     Robot,
     Wall,
@@ -33,47 +177,118 @@ impl std::fmt::Display for MapMember {
     }
 }
 
-impl Solution for Day15 {
-    fn new() -> Self {
-        Self {}
+impl TrySolution for Day15 {
+    type Parsed = Warehouse;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        let (grid, dir_list) = input
+            .split_once("\n\n")
+            .or_else(|| input.split_once("\r\n\r\n"))
+            .ok_or_else(|| SolveError::new("expected a map and a move list"))?;
+
+        let grid = Grid::try_parse(grid, |c| match c {
+            b'.' => Ok(MapMember::Empty),
+            b'@' => Ok(MapMember::Robot),
+            b'#' => Ok(MapMember::Wall),
+            b'O' => Ok(MapMember::Crate),
+            _ => Err(format!("unexpected {:?} on the map", c as char)),
+        })?;
+        check_map(&grid)?;
+
+        let moves = dir_list
+            .char_indices()
+            .filter(|(_, c)| !matches!(c, '\n' | '\r'))
+            .map(|(i, c)| match c {
+                '^' | 'v' | '<' | '>' => Ok(QuadDirection::from(c)),
+                _ => Err(SolveError::at_fragment(
+                    input,
+                    &dir_list[i..],
+                    format!("invalid move {c:?}"),
+                )),
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Warehouse { grid, moves })
     }
 
-    fn part1(&mut self, input: &str) -> String {
-        let (grid, dir_list) = input.split_once("\n\n").unwrap();
+    fn try_part1(&mut self, warehouse: &Self::Parsed) -> Result<Answer, SolveError> {
+        let grid = if self.validate {
+            warehouse.simulate(&mut invariants())?
+        } else {
+            warehouse.simulate(&mut ())?
+        };
 
-        let mut grid = Grid::new(grid, |c| match c {
-            b'.' => MapMember::Empty,
-            b'@' => MapMember::Robot,
-            b'#' => MapMember::Wall,
-            b'O' => MapMember::Crate,
-            _ => unreachable!(),
-        });
+        Ok(gps_sum(&grid, MapMember::Crate).into())
+    }
 
-        let dir_list = dir_list
-            .chars()
-            .filter(|c| *c != '\n')
-            .map(QuadDirection::from)
-            .collect_vec();
-
-        let mut robot_coord = grid
-            .iter_with_coords()
-            .find_map(|(c, m)| match m {
-                MapMember::Robot => Some(c),
-                _ => None,
-            })
-            .unwrap();
+    fn try_part2(&mut self, warehouse: &Self::Parsed) -> Result<Answer, SolveError> {
+        let grid = if self.validate {
+            warehouse.simulate_wide(&mut invariants())?
+        } else {
+            warehouse.simulate_wide(&mut ())?
+        };
 
-        // let mut lagging_direction = QuadDirection::None;
-        // let mut before = vec![];
-        // let mut after = vec![];
+        Ok(gps_sum(&grid, MapMember::CrateLeft).into())
+    }
+}
 
-        for dir in dir_list {
-            // for (line1, line2) in before.iter().zip(after.iter()) {
-            //     println!("{line1} {lagging_direction} {line2}");
-            // }
-            // println!();
+fn gps_sum(grid: &Grid<MapMember>, edge: MapMember) -> usize {
+    grid.iter_with_coords()
+        .filter_map(|(c, m)| (*m == edge).then_some(c.row() as usize * 100 + c.col() as usize))
+        .sum()
+}
+
+/// The simulation only ever looks one tile past the robot or a crate, so the
+/// map has to have walls all round it and a single robot.
+fn check_map(grid: &Grid<MapMember>) -> Result<(), SolveError> {
+    let mut robots = grid
+        .iter_with_coords()
+        .filter(|(_, m)| **m == MapMember::Robot)
+        .map(|(c, _)| c);
+    let at = |c: Coord, message: &str| {
+        SolveError::at(c.row() as usize + 1, c.col() as usize + 1, message)
+    };
+
+    robots
+        .next()
+        .ok_or_else(|| SolveError::new("no robot in the warehouse"))?;
+    if let Some(second) = robots.next() {
+        return Err(at(second, "a second robot in the warehouse"));
+    }
 
-            // before = grid.pretty_print_into_rows();
+    let on_border = |c: Coord| {
+        c.row() == 0 || c.col() == 0 || c.row() == grid.height - 1 || c.col() == grid.width - 1
+    };
+    if let Some((c, _)) = grid
+        .iter_with_coords()
+        .find(|&(c, m)| on_border(c) && *m != MapMember::Wall)
+    {
+        return Err(at(c, "the map has to be walled in"));
+    }
+
+    Ok(())
+}
+
+fn find_robot(grid: &Grid<MapMember>) -> Result<Coord, SolveError> {
+    grid.iter_with_coords()
+        .find_map(|(c, m)| match m {
+            MapMember::Robot => Some(c),
+            _ => None,
+        })
+        .ok_or_else(|| SolveError::new("no robot in the warehouse"))
+}
+
+impl Warehouse {
+    /// Runs the part 1 simulation and returns the final grid.
+    pub fn simulate(&self, recorder: &mut impl Recorder) -> Result<Grid<MapMember>, SolveError> {
+        let mut grid = self.grid.clone();
+        let mut robot_coord = find_robot(&grid)?;
+
+        recorder.record(QuadDirection::None, robot_coord, &grid)?;
+
+        for &dir in &self.moves {
+            instrument::tick("day15.moves")?;
 
             match grid[robot_coord + dir] {
                 MapMember::Empty => {
@@ -82,24 +297,18 @@ impl Solution for Day15 {
                     grid[robot_coord] = MapMember::Robot;
                 }
                 MapMember::Wall => {}
-                MapMember::Crate => {
+                MapMember::Crate => 'push: {
                     let Some(mut coord_of_first_empty) = grid
                         .iter_direction_till(robot_coord, dir, |c| {
                             grid[c] == MapMember::Empty || grid[c] == MapMember::Wall
                         })
                         .find(|c| grid[*c] == MapMember::Empty || grid[*c] == MapMember::Wall)
                     else {
-                        // println!("Can't move crate into wall");
-                        // lagging_direction = dir;
-                        // after = grid.pretty_print_into_rows();
-                        continue;
+                        break 'push;
                     };
 
                     if grid[coord_of_first_empty] == MapMember::Wall {
-                        // println!("Can't move crate into wall");
-                        // lagging_direction = dir;
-                        // after = grid.pretty_print_into_rows();
-                        continue;
+                        break 'push;
                     }
 
                     let mut stop = false;
@@ -116,37 +325,29 @@ impl Solution for Day15 {
 
                     robot_coord += dir;
                 }
+                // parse lets through a single robot and no wide crates
                 _ => unreachable!(),
             }
-            // lagging_direction = dir;
-            // after = grid.pretty_print_into_rows();
-        }
 
-        grid.iter_with_coords()
-            .filter_map(|(c, m)| match m {
-                MapMember::Crate => Some(c.row() as usize * 100 + c.col() as usize),
-                _ => None,
-            })
-            .sum::<usize>()
-            .to_string()
-    }
+            recorder.record(dir, robot_coord, &grid)?;
+        }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("1568399"))
+        Ok(grid)
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let (grid, dir_list) = input.split_once("\n\n").unwrap();
+    /// Runs the part 2 simulation, on the map widened to double width crates,
+    /// and returns the final grid.
+    pub fn simulate_wide(
+        &self,
+        recorder: &mut impl Recorder,
+    ) -> Result<Grid<MapMember>, SolveError> {
+        let small_grid = &self.grid;
 
-        let small_grid = Grid::new(grid, |c| match c {
-            b'.' => MapMember::Empty,
-            b'@' => MapMember::Robot,
-            b'#' => MapMember::Wall,
-            b'O' => MapMember::Crate,
-            _ => unreachable!(),
-        });
-
-        let mut grid = Grid::new_blank(small_grid.width * 2, small_grid.height, MapMember::Wall);
+        let wide = small_grid
+            .width
+            .checked_mul(2)
+            .ok_or_else(|| SolveError::new("the warehouse is too wide to double"))?;
+        let mut grid = Grid::new_blank(wide, small_grid.height, MapMember::Wall);
 
         for (coord, &v) in small_grid.iter_with_coords() {
             grid[Coord::new(coord.row(), coord.col() * 2)] = if v == MapMember::Crate {
@@ -161,40 +362,12 @@ impl Solution for Day15 {
             };
         }
 
-        let dir_list = dir_list
-            .chars()
-            .filter(|c| *c != '\n')
-            .map(QuadDirection::from)
-            .collect_vec();
-
-        let mut robot_coord = grid
-            .iter_with_coords()
-            .find_map(|(c, m)| match m {
-                MapMember::Robot => Some(c),
-                _ => None,
-            })
-            .unwrap();
+        let mut robot_coord = find_robot(&grid)?;
 
-        // let mut lagging_direction = QuadDirection::None;
-        // let mut before: Vec<String> = vec![];
-        // let mut after: Vec<String> = vec![];
+        recorder.record(QuadDirection::None, robot_coord, &grid)?;
 
-        'next_dir: for dir in dir_list {
-            // for (line1, line2) in before.iter().zip(after.iter()) {
-            //     println!("{line1} {lagging_direction} {line2}");
-
-            //     if line1.contains("[.")
-            //         || line2.contains("[.")
-            //         || line1.contains(".]")
-            //         || line2.contains(".]")
-            //     {
-            //         panic!();
-            //     }
-            // }
-
-            // println!();
-
-            // before = grid.pretty_print_into_rows();
+        for &dir in &self.moves {
+            instrument::tick("day15.moves")?;
 
             match grid[robot_coord + dir] {
                 MapMember::Empty => {
@@ -203,12 +376,14 @@ impl Solution for Day15 {
                     grid[robot_coord] = MapMember::Robot;
                 }
                 MapMember::Wall => {}
-                MapMember::CrateLeft | MapMember::CrateRight => {
+                MapMember::CrateLeft | MapMember::CrateRight => 'push: {
                     let mut search_stack = vec![robot_coord];
                     let mut historical_search_stack = AHashSet::new();
                     historical_search_stack.insert(robot_coord);
 
                     while let Some(coord) = search_stack.pop() {
+                        instrument::tick("day15.searched")?;
+
                         match grid[coord + dir] {
                             MapMember::CrateLeft => {
                                 if historical_search_stack
@@ -232,10 +407,8 @@ impl Solution for Day15 {
                                 }
                             }
                             MapMember::Wall => {
-                                // println!("Can't move crate into wall");
-                                // lagging_direction = dir;
-                                // after = grid.pretty_print_into_rows();
-                                continue 'next_dir;
+                                // can't move crates into a wall
+                                break 'push;
                             }
                             _ => {}
                         }
@@ -258,48 +431,29 @@ impl Solution for Day15 {
 
                     robot_coord += dir;
                 }
+                // parse lets through a single robot
                 _ => unreachable!(),
             }
-            // lagging_direction = dir;
-            // after = grid.pretty_print_into_rows();
-        }
 
-        // for (line1, line2) in before.iter().zip(after.iter()) {
-        //     println!("{line1} {lagging_direction} {line2}");
-
-        //     if line1.contains("[.")
-        //         || line2.contains("[.")
-        //         || line1.contains(".]")
-        //         || line2.contains(".]")
-        //     {
-        //         panic!();
-        //     }
-        // }
-
-        grid.iter_with_coords()
-            .filter_map(|(c, m)| match m {
-                MapMember::CrateLeft => Some(c.row() as usize * 100 + c.col() as usize),
-                _ => None,
-            })
-            .sum::<usize>()
-            .to_string()
-    }
+            recorder.record(dir, robot_coord, &grid)?;
+        }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        None
+        Ok(grid)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{InputLocation, Part};
 
     #[test]
     fn test_part1() {
         let mut solution = Day15::new();
 
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"########
 #..O.O.#
 ##@.O..#
@@ -311,11 +465,12 @@ mod tests {
 
 <^^>>>vv<v>>v<<"#
             ),
-            String::from("2028")
+            Ok(Answer::from(2028usize))
         );
 
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"##########
 #..O..O.O#
 #......O.#
@@ -338,8 +493,100 @@ vvv<<^>^v^^><<>>><>^<<><^vv^^<>vvv<>><^^v>^>vv<>v<<<<v<^v>^<^^>>>^<v<v
 ^^>vv<^v^v<vv>^<><v<^v>^^^>>>^^vvv^>vvv<>>>^<^>>>>>^<<^v>^vvv<>^<><<v>
 v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^"#
             ),
-            String::from("10092")
+            Ok(Answer::from(10092usize))
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let crlf = Day15::parse("####\r\n#@O#\r\n####\r\n\r\n<>\r\n<").unwrap();
+        assert_eq!(crlf.grid.width, 4);
+        assert_eq!(crlf.moves.len(), 3);
+
+        let err = Day15::parse("####\n#@X#\n####\n\n<>").unwrap_err();
+        assert_eq!(err.location, Some(InputLocation { line: 2, column: 3 }));
+
+        let err = Day15::parse("####\n#@.#\n###\n\n<>").unwrap_err();
+        assert_eq!(err.message, "row 3 is 3 wide, expected 4");
+
+        let err = Day15::parse("#####\n#@.@#\n#####\n\n>>").unwrap_err();
+        assert_eq!(err.message, "a second robot in the warehouse");
+        assert_eq!(err.location, Some(InputLocation { line: 2, column: 4 }));
+
+        let err = Day15::parse("####\n#@..\n####\n\n>>>").unwrap_err();
+        assert_eq!(err.location, Some(InputLocation { line: 2, column: 4 }));
+
+        let err = Day15::parse("####\n#..#\n####\n\n>").unwrap_err();
+        assert_eq!(err.message, "no robot in the warehouse");
+    }
+
+    #[test]
+    fn test_wide_warehouse() {
+        let width = 20_000;
+        let wall = "#".repeat(width);
+        let input = format!("{wall}\n#@O{}#\n{wall}\n\n>>", ".".repeat(width - 4));
+
+        let warehouse = Day15::parse(&input).unwrap();
+        let grid = warehouse.simulate(&mut ()).unwrap();
+        assert_eq!(gps_sum(&grid, MapMember::Crate), 100 + 4);
+
+        let err = warehouse.simulate_wide(&mut ()).unwrap_err();
+        assert_eq!(err.message, "the warehouse is too wide to double");
+    }
+
+    #[test]
+    fn test_recorder() {
+        let warehouse = Day15::parse("######\n#@O..#\n######\n\n>><>>").unwrap();
+
+        let mut frames: Vec<Frame> = vec![];
+        let grid = warehouse.simulate(&mut frames).unwrap();
+
+        assert_eq!(frames.len(), 6);
+        assert_eq!(frames[0].dir, QuadDirection::None);
+        assert_eq!(
+            frames.iter().map(|f| f.robot.col()).collect::<Vec<_>>(),
+            vec![1, 2, 3, 2, 3, 3]
+        );
+        assert_eq!(
+            frames[5].grid.pretty_print_into_rows(),
+            grid.pretty_print_into_rows()
         );
+        assert_eq!(grid.pretty_print_into_rows()[1], "#..@O#");
+
+        let mut frames: Vec<Frame> = vec![];
+        warehouse.simulate_wide(&mut frames).unwrap();
+        assert_eq!(
+            frames.last().unwrap().grid.pretty_print_into_rows()[1],
+            "##...@[]..##"
+        );
+    }
+
+    #[test]
+    fn test_invariants() {
+        let warehouse = Day15::parse("######\n#@O..#\n######\n\n>>").unwrap();
+        assert!(warehouse.simulate_wide(&mut invariants()).is_ok());
+
+        let mut grid = Grid::new("#@.[]#", |c| match c {
+            b'@' => MapMember::Robot,
+            b'[' => MapMember::CrateLeft,
+            b']' => MapMember::CrateRight,
+            b'.' => MapMember::Empty,
+            _ => MapMember::Wall,
+        });
+        let mut checker = invariants();
+        assert!(checker.check("start", &grid).is_ok());
+
+        grid[Coord::new(0, 2)] = MapMember::Robot;
+        let violation = checker.check("move >", &grid).unwrap_err();
+        assert_eq!(violation.invariant, "exactly one robot");
+        assert_eq!(violation.step, 1);
+        assert_eq!(violation.diff, vec!["#@.[]#   #@@[]# *"]);
+
+        grid[Coord::new(0, 2)] = MapMember::Empty;
+        grid[Coord::new(0, 4)] = MapMember::Empty;
+        let violation = checker.check("move <", &grid).unwrap_err();
+        assert_eq!(violation.invariant, "crates stay whole");
+        assert_eq!(violation.message, "[ at (0, 3) is missing its ]");
     }
 
     #[test]
@@ -347,7 +594,8 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^"#
         let mut solution = Day15::new();
 
         //         assert_eq!(
-        //             solution.part2(
+        //             solution.solve(
+        //                 Part::Two,
         //                 r#"#######
         // #...#.#
         // #.....#
@@ -358,11 +606,12 @@ v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^"#
 
         // <vv<<^^<<^^"#
         //             ),
-        //             String::from("105")
+        //             Ok(Answer::from(105usize))
         //         );
 
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"##########
 #..O..O.O#
 #......O.#
@@ -385,7 +634,7 @@ vvv<<^>^v^^><<>>><>^<<><^vv^^<>vvv<>><^^v>^>vv<>v<<<<v<^v>^<^^>>>^<v<v
 ^^>vv<^v^v<vv>^<><v<^v>^^^>>>^^vvv^>vvv<>>>^<^>>>>>^<<^v>^vvv<>^<><<v>
 v^^>>><<^^<>>^v^<v^vv<>v^<<>^<^v^v><^<<<><<^<v><v<>vv>>v><v^<vv<>v^<<^"#
             ),
-            String::from("9021")
+            Ok(Answer::from(9021usize))
         );
     }
 }
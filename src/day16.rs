@@ -12,18 +12,10 @@ impl Solution for Day16 {
         String::new()
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        None
-    }
-
     fn part2(&mut self, input: &str) -> String {
         let _ = input;
         String::new()
     }
-
-    fn known_solution_part2(&self) -> Option<String> {
-        None
-    }
 }
 
 #[cfg(test)]
@@ -1,148 +1,250 @@
 use itertools::Itertools;
 
-use crate::Solution;
+use crate::{Answer, SolveError, TrySolution};
 
 pub struct Day2 {}
 
-fn check<const SKIP_ONE: bool>(l: Vec<u16>) -> bool {
-    let mut is_asc = false;
-    let mut is_desc = false;
-
-    let mut indexes_to_skip: Option<[usize; 3]> = None;
-
-    for (idx, (a, b)) in l.iter().tuple_windows().enumerate() {
-        if a < b && !is_desc {
-            is_asc = true;
-            match b - a {
-                1..=3 => {}
-                _ => {
-                    if SKIP_ONE {
-                        indexes_to_skip = Some([idx.saturating_sub(1), idx, idx + 1]);
-                        break;
-                    }
-
-                    return false;
-                }
-            }
-        } else if a > b && !is_asc {
-            is_desc = true;
-            match a - b {
-                1..=3 => {}
-                _ => {
-                    if SKIP_ONE {
-                        indexes_to_skip = Some([idx.saturating_sub(1), idx, idx + 1]);
-                        break;
-                    }
-
-                    return false;
-                }
-            }
-        } else {
-            if SKIP_ONE {
-                indexes_to_skip = Some([idx.saturating_sub(1), idx, idx + 1]);
-                break;
-            }
+impl Day2 {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Default for Day2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-            return false;
+const MAX_DELTA: u16 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+}
+
+impl std::fmt::Display for Direction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Direction::Increasing => write!(f, "increasing"),
+            Direction::Decreasing => write!(f, "decreasing"),
         }
     }
+}
 
-    if let Some(indexes_to_skip) = indexes_to_skip {
-        for idx in indexes_to_skip {
-            let mut n = l.clone();
-            if n.get(idx).is_none() {
-                continue;
-            }
+/// Why two adjacent levels make a report unsafe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    TooFar { delta: u16 },
+    DirectionFlip,
+    EqualLevels,
+}
 
-            n.remove(idx);
-            if check::<false>(n) {
-                return true;
-            }
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::TooFar { delta } => write!(f, "delta {delta} > {MAX_DELTA}"),
+            Problem::DirectionFlip => write!(f, "direction flip"),
+            Problem::EqualLevels => write!(f, "equal levels"),
         }
+    }
+}
+
+/// The first unsafe step of a report, from the level before `index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fault {
+    pub index: usize,
+    pub problem: Problem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReportAnalysis {
+    /// Set by the first two levels, unless they are equal.
+    pub direction: Option<Direction>,
+    /// `None` for safe reports.
+    pub fault: Option<Fault>,
+    /// The level whose removal makes an unsafe report safe, if one does.
+    pub fix: Option<usize>,
+}
+
+impl ReportAnalysis {
+    pub fn is_safe(&self) -> bool {
+        self.fault.is_none()
+    }
 
-        false
-    } else {
-        true
+    /// Safe, or made safe by removing a single level.
+    pub fn is_tolerable(&self) -> bool {
+        self.is_safe() || self.fix.is_some()
     }
 }
 
-impl Solution for Day2 {
-    fn new() -> Self {
-        Self {}
+impl std::fmt::Display for ReportAnalysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fault {
+            None => write!(f, "safe")?,
+            Some(Fault { index, problem }) => write!(f, "unsafe at level {index}: {problem}")?,
+        }
+        if let Some(direction) = self.direction {
+            write!(f, ", {direction}")?;
+        }
+        match (self.fault, self.fix) {
+            (Some(_), Some(fix)) => write!(f, ", removing level {fix} fixes it"),
+            (Some(_), None) => write!(f, ", no single removal fixes it"),
+            _ => Ok(()),
+        }
     }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        input
-            .lines()
-            .map(|line| -> u16 {
-                check::<false>(
-                    line.split(" ")
-                        .map(|s| s.parse::<u16>().unwrap())
-                        .collect_vec(),
-                )
-                .into()
-            })
-            .sum::<u16>()
-            .to_string()
+/// Walks the levels, which are `(index, level)` pairs so that a level can be
+/// left out without copying the report.
+fn scan(levels: impl Iterator<Item = (usize, u16)>) -> (Option<Direction>, Option<Fault>) {
+    let mut direction = None;
+
+    for ((_, a), (index, b)) in levels.tuple_windows() {
+        let step = match a.cmp(&b) {
+            std::cmp::Ordering::Less => Direction::Increasing,
+            std::cmp::Ordering::Greater => Direction::Decreasing,
+            std::cmp::Ordering::Equal => {
+                return (
+                    direction,
+                    Some(Fault {
+                        index,
+                        problem: Problem::EqualLevels,
+                    }),
+                );
+            }
+        };
+
+        let problem = match *direction.get_or_insert(step) {
+            expected if expected != step => Some(Problem::DirectionFlip),
+            _ if a.abs_diff(b) > MAX_DELTA => Some(Problem::TooFar {
+                delta: a.abs_diff(b),
+            }),
+            _ => None,
+        };
+
+        if let Some(problem) = problem {
+            return (direction, Some(Fault { index, problem }));
+        }
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some("202".to_string())
+    (direction, None)
+}
+
+pub fn analyze(levels: &[u16]) -> ReportAnalysis {
+    let all = || levels.iter().copied().enumerate();
+    let (direction, fault) = scan(all());
+
+    // only the two levels of the bad step, or the one before them when it
+    // set the wrong direction, can be the culprit
+    let fix = fault.and_then(|fault| {
+        (fault.index.saturating_sub(2)..=fault.index)
+            .find(|&skip| scan(all().filter(|&(i, _)| i != skip)).1.is_none())
+    });
+
+    ReportAnalysis {
+        direction,
+        fault,
+        fix,
     }
+}
+
+impl TrySolution for Day2 {
+    type Parsed = Vec<Vec<u16>>;
+    type Params = ();
 
-    fn part2(&mut self, input: &str) -> String {
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
         input
             .lines()
-            .map(|line| -> u16 {
-                check::<true>(
-                    line.split(" ")
-                        .map(|s| s.parse::<u16>().unwrap())
-                        .collect_vec(),
-                )
-                .into()
+            .map(|line| {
+                line.split_whitespace()
+                    .map(|s| {
+                        s.parse().map_err(|_| {
+                            SolveError::at_fragment(input, s, format!("{s:?} is not a level"))
+                        })
+                    })
+                    .collect()
             })
-            .sum::<u16>()
-            .to_string()
+            .collect()
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some("271".to_string())
+    fn try_part1(&mut self, reports: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(reports
+            .iter()
+            .filter(|levels| analyze(levels).is_safe())
+            .count()
+            .into())
+    }
+
+    fn try_part2(&mut self, reports: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(reports
+            .iter()
+            .filter(|levels| analyze(levels).is_tolerable())
+            .count()
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
-    #[test]
-    fn test_part1() {
-        let mut solution = Day2::new();
-        assert_eq!(
-            solution.part1(
-                r#"7 6 4 2 1
+    const EXAMPLE: &str = r#"7 6 4 2 1
 1 2 7 8 9
 9 7 6 2 1
 1 3 2 4 5
 8 6 4 4 1
-1 3 6 7 9"#
-            ),
-            String::from("2")
-        );
+1 3 6 7 9"#;
+
+    #[test]
+    fn test_part1() {
+        let mut solution = Day2::new();
+        assert_eq!(solution.solve(Part::One, EXAMPLE), Ok(Answer::from(2usize)));
     }
 
     #[test]
     fn test_part2() {
         let mut solution = Day2::new();
+        assert_eq!(solution.solve(Part::Two, EXAMPLE), Ok(Answer::from(4usize)));
+    }
+
+    #[test]
+    fn test_analysis() {
+        let explain = |line: &str| {
+            let levels = Day2::parse(line).unwrap().remove(0);
+            analyze(&levels).to_string()
+        };
+
+        assert_eq!(explain("7 6 4 2 1"), "safe, decreasing");
         assert_eq!(
-            solution.part2(
-                r#"7 6 4 2 1
-1 2 7 8 9
-9 7 6 2 1
-1 3 2 4 5
-8 6 4 4 1
-1 3 6 7 9"#
-            ),
-            String::from("4")
+            explain("1 2 7 8 9"),
+            "unsafe at level 2: delta 5 > 3, increasing, no single removal fixes it"
+        );
+        assert_eq!(
+            explain("1 3 2 4 5"),
+            "unsafe at level 2: direction flip, increasing, removing level 1 fixes it"
+        );
+        assert_eq!(
+            explain("8 6 4 4 1"),
+            "unsafe at level 3: equal levels, decreasing, removing level 2 fixes it"
+        );
+
+        // the first step sets the wrong direction
+        let analysis = analyze(&[5, 6, 4, 3, 2]);
+        assert_eq!(
+            analysis.fault,
+            Some(Fault {
+                index: 2,
+                problem: Problem::DirectionFlip
+            })
         );
+        assert_eq!(analysis.fix, Some(0));
+
+        // removing the last level
+        assert_eq!(analyze(&[1, 2, 3, 9]).fix, Some(3));
+        assert_eq!(analyze(&[4, 4]).direction, None);
+        assert!(analyze(&[4]).is_safe());
     }
 }
@@ -1,72 +1,225 @@
-use regex::Regex;
-
-use crate::Solution;
+use crate::{Answer, SolveError, TrySolution};
 
 pub struct Day3 {}
 
-impl Solution for Day3 {
-    fn new() -> Self {
+impl Day3 {
+    pub fn new() -> Self {
         Self {}
     }
+}
+
+impl Default for Day3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let sol = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
-        sol.captures_iter(input)
-            .map(|capture| {
-                let a = capture[1].parse::<u32>().unwrap();
-                let b = capture[2].parse::<u32>().unwrap();
-                a * b
-            })
-            .sum::<u32>()
-            .to_string()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(u32, u32),
+    Do,
+    Dont,
+}
+
+/// The instructions a [`Lexer`] picks out of corrupted memory, each written
+/// as `name(args)`.
+///
+/// To recognise more of them, wrap [`Standard`] and fall back to it for the
+/// names it knows.
+pub trait InstructionSet {
+    type Instruction<'a>;
+
+    /// Names to look for right before a `(`.
+    fn names(&self) -> &[&'static str];
+
+    /// Longest `args` that [`decode`](InstructionSet::decode) can accept for
+    /// `name`, so the [`Lexer`] gives up on a `(` that is not closed within
+    /// it instead of searching the rest of the input.
+    fn max_args_len(&self, _name: &'static str) -> usize {
+        usize::MAX
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(188116424.to_string())
+    /// Turns `name(args)` into an instruction, or `None` when the arguments
+    /// do not fit and it is just more corruption.
+    fn decode<'a>(&self, name: &'static str, args: &'a str) -> Option<Self::Instruction<'a>>;
+}
+
+/// `mul(a,b)` with one to three digits each, `do()` and `don't()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Standard;
+
+fn small_number(s: &str) -> Option<u32> {
+    if !(1..=3).contains(&s.len()) || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let sol = Regex::new(r"mul\((\d{1,3}),(\d{1,3})\)").unwrap();
+    s.parse().ok()
+}
 
-        let mut new_input = String::new();
+impl InstructionSet for Standard {
+    type Instruction<'a> = Instruction;
+
+    fn names(&self) -> &[&'static str] {
+        &["mul", "do", "don't"]
+    }
 
-        if let Some((a, _)) = input.split_once("don't()") {
-            new_input.push_str(a);
+    fn max_args_len(&self, name: &'static str) -> usize {
+        match name {
+            // three digits, a comma and three digits
+            "mul" => 7,
+            _ => 0,
         }
+    }
 
-        for dontsection in input.split("don't()").skip(1) {
-            for dosection in dontsection.split("do()").skip(1) {
-                new_input.push_str(dosection);
+    fn decode(&self, name: &'static str, args: &str) -> Option<Instruction> {
+        match (name, args) {
+            ("mul", args) => {
+                let (a, b) = args.split_once(',')?;
+                Some(Instruction::Mul(small_number(a)?, small_number(b)?))
             }
+            ("do", "") => Some(Instruction::Do),
+            ("don't", "") => Some(Instruction::Dont),
+            _ => None,
         }
+    }
+}
+
+/// An instruction and where it sits in the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<T> {
+    /// Byte offset of the instruction's name.
+    pub offset: usize,
+    /// Length in bytes, up to and including the `)`.
+    pub len: usize,
+    pub instruction: T,
+}
 
-        sol.captures_iter(&new_input)
-            .map(|capture| {
-                let a = capture[1].parse::<u32>().unwrap();
-                let b = capture[2].parse::<u32>().unwrap();
-                a * b
-            })
-            .sum::<u32>()
-            .to_string()
+/// Streams the instructions out of corrupted memory without copying it.
+///
+/// Jumps from one `(` to the next and checks whether a known name ends right
+/// before it; when several do, the longest wins.
+pub struct Lexer<'a, S = Standard> {
+    input: &'a str,
+    position: usize,
+    set: S,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_set(input, Standard)
     }
+}
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(104245808.to_string())
+impl<'a, S: InstructionSet> Lexer<'a, S> {
+    pub fn with_set(input: &'a str, set: S) -> Self {
+        Self {
+            input,
+            position: 0,
+            set,
+        }
+    }
+}
+
+impl<'a, S: InstructionSet> Iterator for Lexer<'a, S> {
+    type Item = Token<S::Instruction<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(found) = self.input[self.position..].find('(') {
+            let open = self.position + found;
+            let searched_from = self.position;
+            self.position = open + 1;
+
+            let before = &self.input[searched_from..open];
+            let Some(name) = self
+                .set
+                .names()
+                .iter()
+                .copied()
+                .filter(|name| before.ends_with(name))
+                .max_by_key(|name| name.len())
+            else {
+                continue;
+            };
+
+            let args = &self.input.as_bytes()[open + 1..];
+            let window = args
+                .len()
+                .min(self.set.max_args_len(name).saturating_add(1));
+            let Some(close) = args[..window].iter().position(|&b| b == b')') else {
+                continue;
+            };
+            let close = open + 1 + close;
+
+            let Some(instruction) = self.set.decode(name, &self.input[open + 1..close]) else {
+                continue;
+            };
+
+            let offset = open - name.len();
+            self.position = close + 1;
+
+            return Some(Token {
+                offset,
+                len: close + 1 - offset,
+                instruction,
+            });
+        }
+
+        self.position = self.input.len();
+        None
+    }
+}
+
+/// Sum of the products, skipping the ones after a `don't()` until the next
+/// `do()` when `conditionals` is set.
+fn run(tokens: &[Token<Instruction>], conditionals: bool) -> u64 {
+    let mut enabled = true;
+    let mut sum = 0;
+
+    for token in tokens {
+        match token.instruction {
+            Instruction::Mul(a, b) if enabled || !conditionals => {
+                sum += u64::from(a) * u64::from(b);
+            }
+            Instruction::Mul(..) => {}
+            Instruction::Do => enabled = true,
+            Instruction::Dont => enabled = false,
+        }
+    }
+
+    sum
+}
+
+impl TrySolution for Day3 {
+    type Parsed = Vec<Token<Instruction>>;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        Ok(Lexer::new(input).collect())
+    }
+
+    fn try_part1(&mut self, tokens: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(run(tokens, false).into())
+    }
+
+    fn try_part2(&mut self, tokens: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(run(tokens, true).into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day3::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"xmul(2,4)%&mul[3,7]!@^do_not_mul(5,5)+mul(32,64]then(mul(11,8)mul(8,5))"#
             ),
-            String::from("161")
+            Ok(Answer::from(161u64))
         );
     }
 
@@ -74,10 +227,93 @@ mod tests {
     fn test_part2() {
         let mut solution = Day3::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))"#
             ),
-            String::from("48")
+            Ok(Answer::from(48u64))
+        );
+    }
+
+    #[test]
+    fn test_tokens() {
+        let input = "xmul(2,4)&don't()_mul(5,5)+mul(1234,5)mul(mul(11,8)undo()?";
+        let tokens = Lexer::new(input).collect::<Vec<_>>();
+
+        assert_eq!(
+            tokens
+                .iter()
+                .map(|t| (&input[t.offset..t.offset + t.len], t.instruction))
+                .collect::<Vec<_>>(),
+            [
+                ("mul(2,4)", Instruction::Mul(2, 4)),
+                ("don't()", Instruction::Dont),
+                ("mul(5,5)", Instruction::Mul(5, 5)),
+                ("mul(11,8)", Instruction::Mul(11, 8)),
+                ("do()", Instruction::Do),
+            ]
+        );
+        assert_eq!(tokens[0].offset, 1);
+
+        assert_eq!(Lexer::new("mul(1,2").count(), 0);
+        assert_eq!(Lexer::new("mul( 1,2)mul(+1,2)mul(1,2,3)").count(), 0);
+        assert_eq!(Lexer::new("mul(1,2 and then some)").count(), 0);
+    }
+
+    #[test]
+    fn test_unclosed() {
+        // every `mul(` looks only as far as the longest arguments it takes,
+        // not all the way to the `)` at the end
+        let input = format!("{}mul(3,4)", "mul(".repeat(100_000));
+        let tokens = Lexer::new(&input).collect::<Vec<_>>();
+
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0].instruction, Instruction::Mul(3, 4));
+        assert_eq!(tokens[0].offset, 400_000);
+    }
+
+    #[test]
+    fn test_custom_instructions() {
+        #[derive(Debug, PartialEq, Eq)]
+        enum Extended<'a> {
+            Standard(Instruction),
+            Print(&'a str),
+        }
+
+        struct WithPrint;
+
+        impl InstructionSet for WithPrint {
+            type Instruction<'a> = Extended<'a>;
+
+            fn names(&self) -> &[&'static str] {
+                &["mul", "do", "don't", "print"]
+            }
+
+            fn max_args_len(&self, name: &'static str) -> usize {
+                match name {
+                    "print" => usize::MAX,
+                    name => Standard.max_args_len(name),
+                }
+            }
+
+            fn decode<'a>(&self, name: &'static str, args: &'a str) -> Option<Extended<'a>> {
+                match name {
+                    "print" => Some(Extended::Print(args)),
+                    name => Standard.decode(name, args).map(Extended::Standard),
+                }
+            }
+        }
+
+        let instructions = Lexer::with_set("do()#print(hi)mul(3,4)", WithPrint)
+            .map(|t| t.instruction)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            instructions,
+            [
+                Extended::Standard(Instruction::Do),
+                Extended::Print("hi"),
+                Extended::Standard(Instruction::Mul(3, 4)),
+            ]
         );
     }
 }
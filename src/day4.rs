@@ -1,133 +1,83 @@
-use itertools::Itertools;
+use std::convert::Infallible;
 
-use crate::Solution;
+use crate::{
+    Answer, SolveError, TrySolution,
+    direction::OctDirection,
+    grid::{Coord, Grid},
+};
 
 pub struct Day4 {}
 
-#[derive(Debug)]
-struct Matrix {
-    data: Vec<Vec<u8>>,
-    col_count: i16,
-    row_count: i16,
-}
-
-impl Matrix {
-    fn new(input: &str) -> Self {
-        let mut input_array: Vec<Vec<u8>> = vec![];
-        let col_count = input.lines().next().unwrap().chars().count();
-        let row_count = input.lines().count();
-
-        for line in input.lines() {
-            let mut line_array = vec![];
-            for c in line.as_bytes() {
-                line_array.push(*c);
-            }
-            input_array.push(line_array);
-        }
-
-        Self {
-            data: input_array,
-            col_count: col_count as i16,
-            row_count: row_count as i16,
-        }
-    }
-
-    fn get(&self, row: i16, col: i16, inc_row: i16, inc_col: i16) -> Option<u8> {
-        let (row, col) = (row + inc_row, col + inc_col);
-        if row < 0 || col < 0 || col >= self.col_count || row >= self.row_count {
-            None
-        } else {
-            Some(self.data[row as usize][col as usize])
-        }
-    }
-
-    fn iterate_all(&self) -> impl Iterator<Item = (u8, u8, u8, u8)> {
-        (-1..=1).cartesian_product(-1..=1).flat_map(move |(x, y)| {
-            (0..self.row_count)
-                .flat_map(move |row| (0..self.col_count).map(move |col| (row, col)))
-                .flat_map(move |(row, col)| {
-                    if let (Some(a), Some(b), Some(c), Some(d)) = (
-                        self.get(row, col, 0, 0),
-                        self.get(row, col, x, y),
-                        self.get(row, col, x * 2, y * 2),
-                        self.get(row, col, x * 3, y * 3),
-                    ) {
-                        Some((a, b, c, d))
-                    } else {
-                        None
-                    }
-                })
-        })
+impl Day4 {
+    pub fn new() -> Self {
+        Self {}
     }
+}
 
-    fn iterate_all_2(&self) -> impl Iterator<Item = (u8, u8, u8, u8, u8)> {
-        [
-            [(-1, -1), (-1, 1), (1, -1), (1, 1)],
-            [(-1, 1), (1, 1), (-1, -1), (1, -1)],
-            [(-1, 1), (-1, -1), (1, 1), (1, -1)],
-            [(1, 1), (-1, -1), (1, -1), (-1, 1)],
-        ]
-        .iter()
-        .flat_map(move |xys| {
-            (0..self.row_count)
-                .flat_map(move |row| (0..self.col_count).map(move |col| (row, col)))
-                .flat_map(move |(row, col)| {
-                    if let (Some(a), Some(b), Some(c), Some(d), Some(e)) = (
-                        self.get(row, col, xys[0].0, xys[0].1),
-                        self.get(row, col, xys[1].0, xys[1].1),
-                        self.get(row, col, 0, 0),
-                        self.get(row, col, xys[2].0, xys[2].1),
-                        self.get(row, col, xys[3].0, xys[3].1),
-                    ) {
-                        Some((a, b, c, d, e))
-                    } else {
-                        None
-                    }
-                })
-        })
+impl Default for Day4 {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl Solution for Day4 {
-    fn new() -> Self {
-        Self {}
-    }
+impl TrySolution for Day4 {
+    type Parsed = Grid<u8>;
+    type Params = ();
 
-    fn part1(&mut self, input: &str) -> String {
-        Matrix::new(input)
-            .iterate_all()
-            .filter(|c| matches!(c, (b'X', b'M', b'A', b'S')))
-            .count()
-            .to_string()
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        Ok(Grid::try_parse(input, Ok::<_, Infallible>)?)
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some("2618".to_string())
+    fn try_part1(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(grid
+            .iter_with_coords()
+            .filter(|(_, c)| **c == b'X')
+            .map(|(coord, _)| {
+                OctDirection::get_all_directions()
+                    .iter()
+                    .filter(|dir| {
+                        (1..).zip(b"MAS").all(|(distance, c)| {
+                            grid.get(coord + dir.to_coord_offset() * distance) == Some(c)
+                        })
+                    })
+                    .count()
+            })
+            .sum::<usize>()
+            .into())
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        Matrix::new(input)
-            .iterate_all_2()
-            .filter(|c| matches!(c, (b'M', b'S', b'A', b'M', b'S')))
+    fn try_part2(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
+        let is_mas = |coord: Coord, dir: OctDirection| {
+            matches!(
+                (grid.get(coord + dir), grid.get(coord + dir.opposite())),
+                (Some(b'M'), Some(b'S')) | (Some(b'S'), Some(b'M'))
+            )
+        };
+
+        Ok(grid
+            .iter_with_coords()
+            .filter(|(coord, c)| {
+                **c == b'A'
+                    && is_mas(*coord, OctDirection::UpLeft)
+                    && is_mas(*coord, OctDirection::UpRight)
+            })
             .count()
-            .to_string()
-    }
-
-    fn known_solution_part2(&self) -> Option<String> {
-        Some("2011".to_string())
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day4::new();
 
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"....XXMAS.
 .SAMXMS...
 ...S..A...
@@ -139,7 +89,7 @@ S.S.S.S.SS
 ..M.M.M.MM
 .X.X.XMASX"#
             ),
-            String::from("18")
+            Ok(Answer::from(18usize))
         );
     }
 
@@ -148,7 +98,8 @@ S.S.S.S.SS
         let mut solution = Day4::new();
 
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#".M.S......
 ..A..MSMS.
 .M.S.MAA..
@@ -160,7 +111,7 @@ S.S.S.S.S.
 M.M.M.M.M.
 .........."#
             ),
-            String::from("9")
+            Ok(Answer::from(9usize))
         );
     }
 }
@@ -1,159 +1,252 @@
-use ahash::AHashMap;
-use itertools::Itertools;
-use rand::seq::SliceRandom;
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-use crate::Solution;
+use ahash::{AHashMap, AHashSet};
+
+use crate::{Answer, SolveError, TrySolution};
 
 pub struct Day5 {}
 
-impl Solution for Day5 {
-    fn new() -> Self {
+impl Day5 {
+    pub fn new() -> Self {
         Self {}
     }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let mut map: AHashMap<u32, Vec<u32>> = AHashMap::new();
+impl Default for Day5 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let (rules, checks) = input.split_once("\n\n").unwrap();
+/// The `a|b` rules: page `a` has to be printed before page `b` whenever an
+/// update has both.
+#[derive(Debug, Clone, Default)]
+pub struct PageRules {
+    before: AHashMap<u32, AHashSet<u32>>,
+}
 
-        for rule in rules.lines() {
-            let (a, b) = rule.split_once("|").unwrap();
-            map.entry(a.parse::<u32>().unwrap())
-                .or_default()
-                .push(b.parse::<u32>().unwrap());
+/// Two pages of an update the wrong way round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    /// Positions in the update, `first < second`.
+    pub first: usize,
+    pub second: usize,
+    /// The rule they break, `second`'s page before `first`'s.
+    pub rule: (u32, u32),
+}
+
+/// Rules that contradict each other within an update.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrderCycle {
+    /// Each page has to come before the next, the last before the first.
+    pub pages: Vec<u32>,
+}
+
+impl std::fmt::Display for OrderCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the rules go round in a cycle: ")?;
+        for page in &self.pages {
+            write!(f, "{page}|")?;
         }
+        write!(f, "{}", self.pages[0])
+    }
+}
 
-        checks
-            .lines()
-            .map(|check| {
-                check
-                    .split(",")
-                    .map(|x| x.parse::<u32>().unwrap())
-                    .collect_vec()
-            })
-            .filter(|all| {
-                for a in 0..all.len() {
-                    for b in a..all.len() {
-                        if a == b {
-                            continue;
-                        }
-
-                        let a = all[a];
-                        let b = all[b];
-
-                        if map.get(&a).map(|x| x.contains(&b)).unwrap_or(false) {
-                            continue;
-                        } else {
-                            return false;
-                        }
-                    }
-                }
+impl std::error::Error for OrderCycle {}
 
-                true
-            })
-            .map(|all| all[all.len() / 2])
-            .sum::<u32>()
-            .to_string()
+impl From<OrderCycle> for SolveError {
+    fn from(cycle: OrderCycle) -> Self {
+        SolveError::new(cycle.to_string())
     }
+}
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("4609"))
+impl PageRules {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let mut map: AHashMap<u32, Vec<u32>> = AHashMap::new();
+    pub fn with(mut self, first: u32, then: u32) -> Self {
+        self.before.entry(first).or_default().insert(then);
+        self
+    }
 
-        let (rules, checks) = input.split_once("\n\n").unwrap();
+    /// Whether a rule puts `a` before `b`.
+    pub fn precedes(&self, a: u32, b: u32) -> bool {
+        self.before.get(&a).is_some_and(|after| after.contains(&b))
+    }
 
-        for rule in rules.lines() {
-            let (a, b) = rule.split_once("|").unwrap();
-            map.entry(a.parse::<u32>().unwrap())
-                .or_default()
-                .push(b.parse::<u32>().unwrap());
+    pub fn is_ordered(&self, update: &[u32]) -> bool {
+        update
+            .iter()
+            .enumerate()
+            .all(|(i, &a)| update[i + 1..].iter().all(|&b| !self.precedes(b, a)))
+    }
+
+    /// Every pair of pages that breaks a rule.
+    pub fn violations(&self, update: &[u32]) -> Vec<Violation> {
+        let mut violations = vec![];
+
+        for (first, &a) in update.iter().enumerate() {
+            for (second, &b) in update.iter().enumerate().skip(first + 1) {
+                if self.precedes(b, a) {
+                    violations.push(Violation {
+                        first,
+                        second,
+                        rule: (b, a),
+                    });
+                }
+            }
         }
 
-        fn check_one(all: &[u32], map: &AHashMap<u32, Vec<u32>>) -> bool {
-            if all.is_empty() {
-                return true;
+        violations
+    }
+
+    /// Puts the pages of `update` in an order that follows every rule
+    /// between them, with Kahn's algorithm over only the update's pages.
+    ///
+    /// Pages no rule orders keep their relative order, so an update that is
+    /// already ordered comes back unchanged.
+    pub fn sort(&self, update: &[u32]) -> Result<Vec<u32>, OrderCycle> {
+        let n = update.len();
+        let mut successors = vec![vec![]; n];
+        let mut incoming = vec![0; n];
+
+        for (i, &a) in update.iter().enumerate() {
+            for (j, &b) in update.iter().enumerate() {
+                if self.precedes(a, b) {
+                    successors[i].push(j);
+                    incoming[j] += 1;
+                }
             }
+        }
 
-            for a in 0..all.len() {
-                for b in a..all.len() {
-                    if a == b {
-                        continue;
-                    }
+        let mut ready = (0..n)
+            .filter(|&i| incoming[i] == 0)
+            .map(Reverse)
+            .collect::<BinaryHeap<_>>();
+        let mut sorted = Vec::with_capacity(n);
 
-                    let a = all[a];
-                    let b = all[b];
+        while let Some(Reverse(i)) = ready.pop() {
+            sorted.push(update[i]);
 
-                    if map.get(&a).map(|x| x.contains(&b)).unwrap_or(false) {
-                        continue;
-                    } else {
-                        return false;
-                    }
+            for &j in &successors[i] {
+                incoming[j] -= 1;
+                if incoming[j] == 0 {
+                    ready.push(Reverse(j));
                 }
             }
+        }
+
+        if sorted.len() < n {
+            return Err(self.find_cycle(update, &incoming));
+        }
+
+        Ok(sorted)
+    }
 
-            true
+    /// Every page Kahn's algorithm left behind has a predecessor that was
+    /// left behind too, so walking predecessors has to come round.
+    fn find_cycle(&self, update: &[u32], incoming: &[usize]) -> OrderCycle {
+        let stuck = (0..update.len())
+            .filter(|&i| incoming[i] > 0)
+            .collect::<Vec<_>>();
+        let predecessor = |j: usize| {
+            stuck
+                .iter()
+                .copied()
+                .find(|&i| self.precedes(update[i], update[j]))
+                .unwrap()
+        };
+
+        let mut seen = AHashMap::new();
+        let mut path = vec![];
+        let mut current = stuck[0];
+        while !seen.contains_key(&current) {
+            seen.insert(current, path.len());
+            path.push(current);
+            current = predecessor(current);
         }
 
-        checks
+        let mut pages = path[seen[&current]..]
+            .iter()
+            .map(|&i| update[i])
+            .collect::<Vec<_>>();
+        pages.reverse();
+
+        OrderCycle { pages }
+    }
+}
+
+pub struct Manual {
+    rules: PageRules,
+    updates: Vec<Vec<u32>>,
+}
+
+fn parse_page(input: &str, s: &str) -> Result<u32, SolveError> {
+    s.parse()
+        .map_err(|_| SolveError::at_fragment(input, s, format!("{s:?} is not a page")))
+}
+
+fn middle(pages: &[u32]) -> u32 {
+    pages.get(pages.len() / 2).copied().unwrap_or_default()
+}
+
+impl TrySolution for Day5 {
+    type Parsed = Manual;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        let (rules, updates) = input
+            .split_once("\n\n")
+            .ok_or_else(|| SolveError::new("expected rules, a blank line and updates"))?;
+
+        let mut page_rules = PageRules::new();
+        for rule in rules.lines() {
+            let (a, b) = rule
+                .split_once('|')
+                .ok_or_else(|| SolveError::at_fragment(input, rule, "expected `a|b`"))?;
+            page_rules = page_rules.with(parse_page(input, a)?, parse_page(input, b)?);
+        }
+
+        let updates = updates
             .lines()
-            .map(|check| {
-                check
-                    .split(",")
-                    .map(|x| x.parse::<u32>().unwrap())
-                    .collect_vec()
-            })
-            .filter(|all| !check_one(all, &map))
-            .map(|mut all| {
-                let mut result: Vec<u32> = vec![];
-
-                while !check_one(&all, &map) {
-                    // check first for incoming edges
-                    let first = all[0];
-
-                    let incoming_count = all
-                        .iter()
-                        .flat_map(|t| map.get(t).map(|v| (t, v)))
-                        .filter(|to| all.contains(to.0))
-                        .filter(|to| to.1.contains(&first))
-                        .count();
-
-                    if incoming_count == 0 {
-                        // pop the first element
-                        result.push(all.remove(0));
-                    }
-
-                    all.shuffle(&mut rand::thread_rng());
-                }
+            .map(|update| update.split(',').map(|s| parse_page(input, s)).collect())
+            .collect::<Result<_, _>>()?;
 
-                for x in all {
-                    result.push(x);
-                }
+        Ok(Manual {
+            rules: page_rules,
+            updates,
+        })
+    }
 
-                result
-            })
-            .map(|all| all[all.len() / 2])
+    fn try_part1(&mut self, manual: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(manual
+            .updates
+            .iter()
+            .filter(|update| manual.rules.is_ordered(update))
+            .map(|update| middle(update))
             .sum::<u32>()
-            .to_string()
+            .into())
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("5723"))
+    fn try_part2(&mut self, manual: &Self::Parsed) -> Result<Answer, SolveError> {
+        let mut sum = 0;
+        for update in &manual.updates {
+            if !manual.rules.is_ordered(update) {
+                sum += middle(&manual.rules.sort(update)?);
+            }
+        }
+
+        Ok(sum.into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
-    #[test]
-    fn test_part1() {
-        let mut solution = Day5::new();
-        assert_eq!(
-            solution.part1(
-                r#"47|53
+    const EXAMPLE: &str = r#"47|53
 97|13
 97|61
 97|47
@@ -180,47 +273,60 @@ mod tests {
 75,29,13
 75,97,47,61,53
 61,13,29
-97,13,75,29,47"#
-            ),
-            String::from("143")
-        );
+97,13,75,29,47"#;
+
+    #[test]
+    fn test_part1() {
+        let mut solution = Day5::new();
+        assert_eq!(solution.solve(Part::One, EXAMPLE), Ok(Answer::from(143u32)));
     }
 
     #[test]
     fn test_part2() {
         let mut solution = Day5::new();
+        assert_eq!(solution.solve(Part::Two, EXAMPLE), Ok(Answer::from(123u32)));
+    }
+
+    #[test]
+    fn test_page_rules() {
+        let rules = Day5::parse(EXAMPLE).unwrap().rules;
+
         assert_eq!(
-            solution.part2(
-                r#"47|53
-97|13
-97|61
-97|47
-75|29
-61|13
-75|53
-29|13
-97|29
-53|29
-61|53
-97|53
-61|29
-47|13
-75|47
-97|75
-47|61
-75|61
-47|29
-75|13
-53|13
+            rules.sort(&[97, 13, 75, 29, 47]),
+            Ok(vec![97, 75, 47, 29, 13])
+        );
+        assert_eq!(
+            rules.sort(&[75, 47, 61, 53, 29]),
+            Ok(vec![75, 47, 61, 53, 29])
+        );
 
-75,47,61,53,29
-97,61,53,29,13
-75,29,13
-75,97,47,61,53
-61,13,29
-97,13,75,29,47"#
-            ),
-            String::from("123")
+        assert_eq!(
+            rules.violations(&[61, 13, 29]),
+            [Violation {
+                first: 1,
+                second: 2,
+                rule: (29, 13)
+            }]
         );
+        assert!(rules.violations(&[75, 29, 13]).is_empty());
+
+        // pages without rules between them stay put
+        let rules = PageRules::new().with(3, 1);
+        assert_eq!(rules.sort(&[5, 1, 4, 3, 2]), Ok(vec![5, 4, 3, 1, 2]));
+    }
+
+    #[test]
+    fn test_cycle() {
+        let rules = PageRules::new().with(1, 2).with(2, 3).with(3, 1).with(4, 1);
+
+        let cycle = rules.sort(&[4, 3, 2, 1]).unwrap_err();
+        let mut rotated = cycle.pages.clone();
+        let first = rotated.iter().position(|&p| p == 1).unwrap();
+        rotated.rotate_left(first);
+        assert_eq!(rotated, [1, 2, 3]);
+        assert!(cycle.to_string().contains('|'));
+
+        // the cycle only matters when the update has all of its pages
+        assert_eq!(rules.sort(&[3, 2, 4]), Ok(vec![2, 3, 4]));
     }
 }
@@ -4,9 +4,10 @@ use ahash::AHashSet;
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 
 use crate::{
-    Solution,
+    Answer, SolveError, TrySolution, cycle,
     direction::QuadDirection,
     grid::{Coord, Grid},
+    instrument::{self, BudgetExceeded},
 };
 
 #[derive(Debug, Clone, Copy)]
@@ -50,10 +51,11 @@ fn get_visited_cells_till_exit(
     grid: &Grid<GridType>,
     mut coord: Coord,
     mut dir: QuadDirection,
-) -> AHashSet<Coord> {
+) -> Result<AHashSet<Coord>, BudgetExceeded> {
     let mut visited_cells = AHashSet::new();
 
     loop {
+        instrument::tick("day6.steps")?;
         visited_cells.insert(coord);
 
         match next_val(grid, coord, dir) {
@@ -69,117 +71,111 @@ fn get_visited_cells_till_exit(
         }
     }
 
-    visited_cells
+    Ok(visited_cells)
 }
 
+#[derive(Default)]
 pub struct Day6 {}
 
-impl Solution for Day6 {
-    fn new() -> Self {
+impl Day6 {
+    pub fn new() -> Self {
         Self {}
     }
+}
+
+#[derive(Debug)]
+pub struct Lab {
+    grid: Grid<GridType>,
+    start: Coord,
+    dir: QuadDirection,
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| match c {
-            b'.' => GridType::Empty,
-            b'#' => GridType::Wall,
-            b'^' => GridType::Direction(QuadDirection::Up),
-            b'v' => GridType::Direction(QuadDirection::Down),
-            b'>' => GridType::Direction(QuadDirection::Right),
-            b'<' => GridType::Direction(QuadDirection::Left),
-            _ => unreachable!(),
-        });
+impl TrySolution for Day6 {
+    type Parsed = Lab;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        let grid = Grid::try_parse(input, |c| match c {
+            b'.' => Ok(GridType::Empty),
+            b'#' => Ok(GridType::Wall),
+            b'^' => Ok(GridType::Direction(QuadDirection::Up)),
+            b'v' => Ok(GridType::Direction(QuadDirection::Down)),
+            b'>' => Ok(GridType::Direction(QuadDirection::Right)),
+            b'<' => Ok(GridType::Direction(QuadDirection::Left)),
+            _ => Err(format!("unexpected {:?} on the map", c as char)),
+        })?;
 
         // find the first direction
-        let (coord, dir) = grid
+        let (start, dir) = grid
             .iter_with_coords()
             .find_map(|(coord, val)| match val {
-                GridType::Direction(dir) => Some((coord, dir)),
+                GridType::Direction(dir) => Some((coord, *dir)),
                 _ => None,
             })
-            .unwrap();
-
-        let visited_cells = get_visited_cells_till_exit(&grid, coord, *dir);
+            .ok_or_else(|| SolveError::new("no guard on the map"))?;
 
-        visited_cells.len().to_string()
+        Ok(Lab { grid, start, dir })
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("5131"))
-    }
+    fn try_part1(&mut self, lab: &Self::Parsed) -> Result<Answer, SolveError> {
+        let visited_cells = get_visited_cells_till_exit(&lab.grid, lab.start, lab.dir)?;
 
-    fn part2(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| match c {
-            b'.' => GridType::Empty,
-            b'#' => GridType::Wall,
-            b'^' => GridType::Direction(QuadDirection::Up),
-            b'v' => GridType::Direction(QuadDirection::Down),
-            b'>' => GridType::Direction(QuadDirection::Right),
-            b'<' => GridType::Direction(QuadDirection::Left),
-            _ => unreachable!(),
-        });
+        Ok(visited_cells.len().into())
+    }
 
-        // find the first direction
-        let (starting_coord, starting_dir) = grid
-            .iter_with_coords()
-            .find_map(|(coord, val)| match val {
-                GridType::Direction(dir) => Some((coord, dir)),
-                _ => None,
-            })
-            .unwrap();
+    fn try_part2(&mut self, lab: &Self::Parsed) -> Result<Answer, SolveError> {
+        let Lab {
+            grid,
+            start: starting_coord,
+            dir: starting_dir,
+        } = lab;
 
-        let visited_cells = get_visited_cells_till_exit(&grid, starting_coord, *starting_dir);
+        let visited_cells = get_visited_cells_till_exit(grid, *starting_coord, *starting_dir)?;
+        let counters = instrument::current();
 
-        visited_cells
+        Ok(visited_cells
             .par_iter()
             .map(|to_edit_coord| {
-                let mut coord = starting_coord;
-                let mut dir = *starting_dir;
-                let mut visited_cells = AHashSet::new();
-
-                visited_cells.insert((coord, dir));
+                // walking off the map is a state that repeats forever, so
+                // every walk ends in a cycle and only loops don't end there
+                let cycle = cycle::brent(Some((*starting_coord, *starting_dir)), |state| {
+                    let (coord, dir) = (*state)?;
 
-                loop {
                     let next = if *to_edit_coord == (coord + dir.to_coord_offset()) {
                         NextResult::HasBlock
                     } else {
-                        next_val(&grid, coord, dir)
+                        next_val(grid, coord, dir)
                     };
 
                     match next {
-                        NextResult::HasBlock => {
-                            dir = dir.rotate_right();
-                        }
-                        NextResult::Empty => {
-                            coord += dir.to_coord_offset();
-                            if !visited_cells.insert((coord, dir)) {
-                                return 1;
-                            }
-                        }
-                        NextResult::OutOfBounds => {
-                            return 0;
-                        }
+                        NextResult::HasBlock => Some((coord, dir.rotate_right())),
+                        NextResult::Empty => Some((coord + dir.to_coord_offset(), dir)),
+                        NextResult::OutOfBounds => None,
                     }
-                }
-            })
-            .sum::<u16>()
-            .to_string()
-    }
+                });
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some("1784".to_string())
+                // ticking every step would have all workers fight over one
+                // counter, so each walk is counted once it ends; the workers
+                // have not entered the run's context, hence the handle
+                counters.tick_by("day6.steps", (cycle.start + cycle.length) as u64)?;
+                Ok(u16::from(cycle.entry.is_some()))
+            })
+            .sum::<Result<u16, BudgetExceeded>>()?
+            .into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day6::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"....#.....
 .........#
 ..........
@@ -191,7 +187,7 @@ mod tests {
 #.........
 ......#..."#
             ),
-            String::from("41")
+            Ok(Answer::from(41u16))
         );
     }
 
@@ -199,7 +195,8 @@ mod tests {
     fn test_part2() {
         let mut solution = Day6::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"....#.....
 .........#
 ..........
@@ -211,7 +208,19 @@ mod tests {
 #.........
 ......#..."#
             ),
-            String::from("6")
+            Ok(Answer::from(6u16))
         );
     }
+
+    #[test]
+    fn test_bad_input() {
+        let err = Day6::parse("..#\n.^.\n..?").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(crate::InputLocation { line: 3, column: 3 })
+        );
+        assert_eq!(err.message, "unexpected '?' on the map");
+
+        assert!(Day6::parse("...\r\n.^.\r\n...\r\n").is_ok());
+    }
 }
@@ -1,100 +1,328 @@
-use crate::Solution;
+use crate::{Answer, SolveError, TrySolution};
 
 pub struct Day7 {}
 
-fn recur_equation_solvable(output: usize, inputs: &[usize], carry: usize) -> bool {
-    if inputs.is_empty() {
-        return output == carry;
+impl Day7 {
+    pub fn new() -> Self {
+        Self {}
     }
+}
 
-    if carry > output {
-        return false;
+impl Default for Day7 {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    // try addition or multiplication of the next number
-    recur_equation_solvable(output, &inputs[1..], carry + inputs[0])
-        || recur_equation_solvable(output, &inputs[1..], carry * inputs[0])
+/// Which left operands an [`Operator`] can turn into a given result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inverse {
+    None,
+    Left(u64),
+    /// Every left operand does, like anything times zero.
+    Any,
 }
 
-fn concat(left: usize, right: usize) -> usize {
-    left * 10usize.pow((right as f64).log(10.0).floor() as u32 + 1) + right
+/// A binary operator between the numbers of an equation, evaluated strictly
+/// left to right.
+///
+/// The solver works backwards from the target, so besides applying itself an
+/// operator has to say which left operand gives a result; that is where all
+/// the pruning comes from.
+pub trait Operator {
+    fn symbol(&self) -> &str;
+
+    /// `None` when the result does not fit.
+    fn apply(&self, left: u64, right: u64) -> Option<u64>;
+
+    fn invert(&self, result: u64, right: u64) -> Inverse;
 }
 
-fn recur_equation_solvable_concat(output: usize, inputs: &[usize], carry: usize) -> bool {
-    if inputs.is_empty() {
-        return output == carry;
+pub struct Add;
+pub struct Mul;
+/// Appends the digits of the right operand to the left one.
+pub struct Concat;
+
+impl Operator for Add {
+    fn symbol(&self) -> &str {
+        "+"
     }
 
-    if carry > output {
-        return false;
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_add(right)
     }
 
-    // try addition or multiplication of the next number
-    recur_equation_solvable_concat(output, &inputs[1..], carry + inputs[0])
-        || recur_equation_solvable_concat(output, &inputs[1..], carry * inputs[0])
-        || recur_equation_solvable_concat(output, &inputs[1..], concat(carry, inputs[0]))
+    fn invert(&self, result: u64, right: u64) -> Inverse {
+        result
+            .checked_sub(right)
+            .map_or(Inverse::None, Inverse::Left)
+    }
 }
 
-impl Solution for Day7 {
-    fn new() -> Self {
-        Self {}
+impl Operator for Mul {
+    fn symbol(&self) -> &str {
+        "*"
     }
 
-    fn part1(&mut self, input: &str) -> String {
-        input
-            .lines()
-            .filter_map(|line| {
-                let (output, inputs) = line.split_once(": ").unwrap();
-                let inputs: Vec<_> = inputs
-                    .split_whitespace()
-                    .map(|s| s.parse::<usize>().unwrap())
-                    .collect();
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(right)
+    }
+
+    fn invert(&self, result: u64, right: u64) -> Inverse {
+        match (result, right) {
+            (0, 0) => Inverse::Any,
+            (_, 0) => Inverse::None,
+            _ if result.is_multiple_of(right) => Inverse::Left(result / right),
+            _ => Inverse::None,
+        }
+    }
+}
+
+/// The power of ten with as many zeros as `n` has digits.
+fn digit_shift(n: u64) -> Option<u64> {
+    10u64.checked_pow(n.checked_ilog10().unwrap_or(0) + 1)
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> &str {
+        "||"
+    }
+
+    fn apply(&self, left: u64, right: u64) -> Option<u64> {
+        left.checked_mul(digit_shift(right)?)?.checked_add(right)
+    }
+
+    fn invert(&self, result: u64, right: u64) -> Inverse {
+        let Some(shift) = digit_shift(right) else {
+            return Inverse::None;
+        };
+
+        if result % shift == right {
+            Inverse::Left(result / shift)
+        } else {
+            Inverse::None
+        }
+    }
+}
+
+pub const PART1_OPERATORS: &[&dyn Operator] = &[&Add, &Mul];
+pub const PART2_OPERATORS: &[&dyn Operator] = &[&Add, &Mul, &Concat];
+
+/// One line of the calibration: `target: numbers...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Equation {
+    pub target: u64,
+    pub numbers: Vec<u64>,
+}
+
+/// The operators between the numbers of an [`Equation`], in order.
+pub type Assignment<'a> = Vec<&'a dyn Operator>;
+
+impl Equation {
+    /// Left to right value of the numbers with `operators` between them.
+    pub fn evaluate(&self, operators: &[&dyn Operator]) -> Option<u64> {
+        let (&first, rest) = self.numbers.split_first()?;
+
+        rest.iter()
+            .zip(operators)
+            .try_fold(first, |left, (&right, op)| op.apply(left, right))
+    }
+
+    /// The first assignment of `operators` that makes the equation true.
+    pub fn solve<'a>(&self, operators: &[&'a dyn Operator]) -> Option<Assignment<'a>> {
+        let mut found = vec![];
+        self.search(operators, true, &mut found);
+        found.pop()
+    }
+
+    /// Every assignment of `operators` that makes the equation true.
+    pub fn solutions<'a>(&self, operators: &[&'a dyn Operator]) -> Vec<Assignment<'a>> {
+        let mut found = vec![];
+        self.search(operators, false, &mut found);
+        found
+    }
+
+    fn search<'a>(
+        &self,
+        operators: &[&'a dyn Operator],
+        first_only: bool,
+        found: &mut Vec<Assignment<'a>>,
+    ) {
+        if self.numbers.is_empty() {
+            return;
+        }
+
+        backward(
+            self.target,
+            &self.numbers,
+            operators,
+            &mut vec![],
+            first_only,
+            found,
+        );
+    }
 
-                let output = output.parse::<usize>().unwrap();
+    /// Writes the equation out with `operators` filled in, like
+    /// `190 = 10 * 19`.
+    pub fn render(&self, operators: &[&dyn Operator]) -> String {
+        let mut s = format!("{} =", self.target);
 
-                recur_equation_solvable(output, &inputs[1..], inputs[0]).then_some(output)
-            })
-            .sum::<usize>()
-            .to_string()
+        for (i, number) in self.numbers.iter().enumerate() {
+            if i > 0 {
+                s += &format!(" {}", operators[i - 1].symbol());
+            }
+            s += &format!(" {number}");
+        }
+
+        s
+    }
+}
+
+/// Peels operators off the end of `numbers`, `suffix` holding the ones
+/// chosen so far from the last backwards. Returns whether to stop.
+fn backward<'a>(
+    target: u64,
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+    suffix: &mut Vec<&'a dyn Operator>,
+    first_only: bool,
+    found: &mut Vec<Assignment<'a>>,
+) -> bool {
+    let (&last, rest) = numbers.split_last().unwrap();
+    if rest.is_empty() {
+        if last == target {
+            found.push(suffix.iter().rev().copied().collect());
+        }
+        return first_only && !found.is_empty();
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some("3312271365652".to_string())
+    for &op in operators {
+        suffix.push(op);
+
+        let stop = match op.invert(target, last) {
+            Inverse::None => false,
+            Inverse::Left(left) => backward(left, rest, operators, suffix, first_only, found),
+            Inverse::Any => forward(rest, operators, suffix, first_only, found),
+        };
+
+        suffix.pop();
+        if stop {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Every assignment for `numbers` that evaluates at all, each followed by
+/// the operators in `suffix`.
+fn forward<'a>(
+    numbers: &[u64],
+    operators: &[&'a dyn Operator],
+    suffix: &[&'a dyn Operator],
+    first_only: bool,
+    found: &mut Vec<Assignment<'a>>,
+) -> bool {
+    fn go<'a>(
+        value: u64,
+        numbers: &[u64],
+        operators: &[&'a dyn Operator],
+        prefix: &mut Vec<&'a dyn Operator>,
+        suffix: &[&'a dyn Operator],
+        first_only: bool,
+        found: &mut Vec<Assignment<'a>>,
+    ) -> bool {
+        let Some((&next, rest)) = numbers.split_first() else {
+            found.push(prefix.iter().chain(suffix.iter().rev()).copied().collect());
+            return first_only;
+        };
+
+        for &op in operators {
+            let Some(value) = op.apply(value, next) else {
+                continue;
+            };
+
+            prefix.push(op);
+            let stop = go(value, rest, operators, prefix, suffix, first_only, found);
+            prefix.pop();
+            if stop {
+                return true;
+            }
+        }
+
+        false
     }
 
-    fn part2(&mut self, input: &str) -> String {
+    let (&first, rest) = numbers.split_first().unwrap();
+    go(
+        first,
+        rest,
+        operators,
+        &mut vec![],
+        suffix,
+        first_only,
+        found,
+    )
+}
+
+fn parse_equation(input: &str, line: &str) -> Result<Equation, SolveError> {
+    let number = |s: &str| {
+        s.parse::<u64>()
+            .map_err(|_| SolveError::at_fragment(input, s, format!("{s:?} is not a number")))
+    };
+
+    let (target, numbers) = line
+        .split_once(": ")
+        .ok_or_else(|| SolveError::at_fragment(input, line, "expected `target: numbers`"))?;
+
+    let numbers = numbers
+        .split_whitespace()
+        .map(number)
+        .collect::<Result<Vec<_>, _>>()?;
+    if numbers.is_empty() {
+        return Err(SolveError::at_fragment(input, line, "no numbers"));
+    }
+
+    Ok(Equation {
+        target: number(target)?,
+        numbers,
+    })
+}
+
+fn calibration(equations: &[Equation], operators: &[&dyn Operator]) -> u64 {
+    equations
+        .iter()
+        .filter(|equation| equation.solve(operators).is_some())
+        .map(|equation| equation.target)
+        .sum()
+}
+
+impl TrySolution for Day7 {
+    type Parsed = Vec<Equation>;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
         input
             .lines()
-            .filter_map(|line| {
-                let (output, inputs) = line.split_once(": ").unwrap();
-                let inputs: Vec<_> = inputs
-                    .split_whitespace()
-                    .map(|s| s.parse::<usize>().unwrap())
-                    .collect();
-
-                let output = output.parse::<usize>().unwrap();
+            .map(|line| parse_equation(input, line))
+            .collect()
+    }
 
-                recur_equation_solvable_concat(output, &inputs[1..], inputs[0]).then_some(output)
-            })
-            .sum::<usize>()
-            .to_string()
+    fn try_part1(&mut self, equations: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(calibration(equations, PART1_OPERATORS).into())
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some("509463489296712".to_string())
+    fn try_part2(&mut self, equations: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(calibration(equations, PART2_OPERATORS).into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
-    #[test]
-    fn test_part1() {
-        let mut solution = Day7::new();
-        assert_eq!(
-            solution.part1(
-                r#"190: 10 19
+    const EXAMPLE: &str = r#"190: 10 19
 3267: 81 40 27
 83: 17 5
 156: 15 6
@@ -102,9 +330,14 @@ mod tests {
 161011: 16 10 13
 192: 17 8 14
 21037: 9 7 18 13
-292: 11 6 16 20"#
-            ),
-            String::from("3749")
+292: 11 6 16 20"#;
+
+    #[test]
+    fn test_part1() {
+        let mut solution = Day7::new();
+        assert_eq!(
+            solution.solve(Part::One, EXAMPLE),
+            Ok(Answer::from(3749u64))
         );
     }
 
@@ -112,18 +345,80 @@ mod tests {
     fn test_part2() {
         let mut solution = Day7::new();
         assert_eq!(
-            solution.part2(
-                r#"190: 10 19
-3267: 81 40 27
-83: 17 5
-156: 15 6
-7290: 6 8 6 15
-161011: 16 10 13
-192: 17 8 14
-21037: 9 7 18 13
-292: 11 6 16 20"#
-            ),
-            String::from("11387")
+            solution.solve(Part::Two, EXAMPLE),
+            Ok(Answer::from(11387u64))
+        );
+    }
+
+    fn equation(line: &str) -> Equation {
+        parse_equation(line, line).unwrap()
+    }
+
+    #[test]
+    fn test_assignments() {
+        let eq = equation("190: 10 19");
+        let ops = eq.solve(PART1_OPERATORS).unwrap();
+        assert_eq!(eq.render(&ops), "190 = 10 * 19");
+
+        let eq = equation("3267: 81 40 27");
+        let mut all = eq
+            .solutions(PART1_OPERATORS)
+            .iter()
+            .map(|ops| eq.render(ops))
+            .collect::<Vec<_>>();
+        all.sort();
+        assert_eq!(all, ["3267 = 81 * 40 + 27", "3267 = 81 + 40 * 27"]);
+
+        let eq = equation("7290: 6 8 6 15");
+        let ops = eq.solve(PART2_OPERATORS).unwrap();
+        assert_eq!(eq.render(&ops), "7290 = 6 * 8 || 6 * 15");
+        assert_eq!(eq.evaluate(&ops), Some(7290));
+
+        assert_eq!(
+            equation("83: 17 5").solve(PART2_OPERATORS).map(|_| ()),
+            None
         );
     }
+
+    #[test]
+    fn test_concat() {
+        for (left, right, result) in [(1, 10, 110), (99, 100, 99100), (12, 0, 120), (0, 7, 7)] {
+            assert_eq!(Concat.apply(left, right), Some(result));
+            assert_eq!(Concat.invert(result, right), Inverse::Left(left));
+        }
+
+        assert_eq!(Concat.apply(999_999_999_999, 10_000_000), None);
+        assert_eq!(Concat.apply(1, u64::MAX), None);
+        assert_eq!(Concat.invert(u64::MAX, u64::MAX), Inverse::None);
+        assert_eq!(Concat.invert(1998, 99), Inverse::None);
+    }
+
+    #[test]
+    fn test_zero_and_custom_operators() {
+        // any left operand times zero is zero, as long as it evaluates
+        let eq = equation("0: 5 3 0");
+        assert_eq!(eq.solutions(PART1_OPERATORS).len(), 2);
+
+        struct Sub;
+
+        impl Operator for Sub {
+            fn symbol(&self) -> &str {
+                "-"
+            }
+
+            fn apply(&self, left: u64, right: u64) -> Option<u64> {
+                left.checked_sub(right)
+            }
+
+            fn invert(&self, result: u64, right: u64) -> Inverse {
+                result
+                    .checked_add(right)
+                    .map_or(Inverse::None, Inverse::Left)
+            }
+        }
+
+        let eq = equation("14: 10 3 2");
+        let ops = eq.solve(&[&Add, &Mul, &Sub]).unwrap();
+        assert_eq!(eq.render(&ops), "14 = 10 - 3 * 2");
+    }
 }
@@ -1,25 +1,40 @@
 use itertools::{Itertools, chain};
 
-use crate::{Solution, grid::Grid};
+use std::convert::Infallible;
+
+use crate::{Answer, SolveError, TrySolution, grid::Grid};
 
 pub struct Day8 {}
 
-impl Solution for Day8 {
-    fn new() -> Self {
+impl Day8 {
+    pub fn new() -> Self {
         Self {}
     }
+}
+
+impl Default for Day8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrySolution for Day8 {
+    type Parsed = Grid<u8>;
+    type Params = ();
 
-    fn part1(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| c);
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        Ok(Grid::try_parse(input, Ok::<_, Infallible>)?)
+    }
 
+    fn try_part1(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
         let binding = grid
             .iter_with_coords()
             .filter(|(_, c)| **c != b'.')
             .into_group_map_by(|(_, c)| **c);
 
         let non_empty_grid_locations = binding
-            .into_iter()
-            .flat_map(|(_, v)| {
+            .into_values()
+            .flat_map(|v| {
                 v.into_iter()
                     .map(|(coord, _)| coord)
                     .combinations(2)
@@ -34,24 +49,18 @@ impl Solution for Day8 {
             .filter(|c| grid.is_coord_in_bounds(*c))
             .unique();
 
-        non_empty_grid_locations.count().to_string()
-    }
-
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("409"))
+        Ok(non_empty_grid_locations.count().into())
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let grid = Grid::new(input, |c| c);
-
+    fn try_part2(&mut self, grid: &Self::Parsed) -> Result<Answer, SolveError> {
         let binding = grid
             .iter_with_coords()
             .filter(|(_, c)| **c != b'.')
             .into_group_map_by(|(_, c)| **c);
 
         let non_empty_grid_locations = binding
-            .into_iter()
-            .flat_map(|(_, v)| {
+            .into_values()
+            .flat_map(|v| {
                 v.into_iter()
                     .map(|(coord, _)| coord)
                     .combinations(2)
@@ -73,23 +82,21 @@ impl Solution for Day8 {
             })
             .unique();
 
-        non_empty_grid_locations.count().to_string()
-    }
-
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("1308"))
+        Ok(non_empty_grid_locations.count().into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day8::new();
         assert_eq!(
-            solution.part1(
+            solution.solve(
+                Part::One,
                 r#"............
 ........0...
 .....0......
@@ -103,7 +110,7 @@ mod tests {
 ............
 ............"#
             ),
-            String::from("14")
+            Ok(Answer::from(14usize))
         );
     }
 
@@ -111,7 +118,8 @@ mod tests {
     fn test_part2() {
         let mut solution = Day8::new();
         assert_eq!(
-            solution.part2(
+            solution.solve(
+                Part::Two,
                 r#"............
 ........0...
 .....0......
@@ -125,7 +133,7 @@ mod tests {
 ............
 ............"#
             ),
-            String::from("34")
+            Ok(Answer::from(34usize))
         );
     }
 }
@@ -1,180 +1,225 @@
-use std::num::NonZeroU16;
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-use itertools::Itertools;
-
-use crate::Solution;
+use crate::{Answer, SolveError, TrySolution};
 
 pub struct Day9 {}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Space {
-    File(NonZeroU16),
-    Empty,
+impl Day9 {
+    pub fn new() -> Self {
+        Self {}
+    }
 }
 
-impl Solution for Day9 {
-    fn new() -> Self {
-        Self {}
+impl Default for Day9 {
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn part1(&mut self, input: &str) -> String {
-        let mut file_id = NonZeroU16::new(1).unwrap();
+/// Consecutive blocks that belong to one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extent {
+    pub id: usize,
+    pub start: usize,
+    pub len: usize,
+}
 
-        assert!(size_of::<Space>() == size_of::<u16>());
+/// Free spans are at most this long in a dense disk map, one digit each;
+/// longer ones share the heap of this length.
+const MAX_SPAN: usize = 9;
 
-        let arr = input
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as usize)
-            .enumerate()
-            .map(|(i, c)| {
-                (
-                    if i % 2 == 0 {
-                        let new = Space::File(file_id);
-                        file_id = file_id.checked_add(1).unwrap();
-                        new
-                    } else {
-                        Space::Empty
-                    },
-                    c,
-                )
-            })
-            .flat_map(|(file, c)| std::iter::repeat_n(file, c))
-            .collect_vec();
+/// The disk as runs of file blocks rather than one entry per block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskMap {
+    /// Sorted by `start`.
+    extents: Vec<Extent>,
+    /// Number of blocks, free ones included.
+    size: usize,
+}
 
-        let mut read_backwards_files = arr
-            .iter()
-            .enumerate()
-            .rev()
-            .filter(|s| matches!(*s.1, Space::File(_)));
+impl DiskMap {
+    /// Reads the dense format, alternating file and free span lengths.
+    pub fn parse(input: &str) -> Result<Self, SolveError> {
+        let mut extents = vec![];
+        let mut size = 0;
 
-        let mut stop_index = arr.len();
+        for (i, c) in input.char_indices() {
+            let len = c.to_digit(10).ok_or_else(|| {
+                SolveError::at_fragment(input, &input[i..], format!("{c:?} is not a length"))
+            })? as usize;
 
-        arr.iter()
-            .enumerate()
-            .map_while(|(i, s)| match s {
-                Space::File(c) if i <= stop_index => Some((i, Space::File(*c))),
-                _ => {
-                    let (index, next) = read_backwards_files.next().unwrap();
-                    stop_index = index - 1;
-                    if i >= index || i >= stop_index {
-                        None
-                    } else {
-                        Some((i, *next))
-                    }
-                }
-            })
-            .map(|(i, s)| match s {
-                Space::File(c) => (c.get() as usize - 1) * i,
-                _ => unreachable!(),
-            })
-            .sum::<usize>()
-            .to_string()
+            if i % 2 == 0 && len > 0 {
+                extents.push(Extent {
+                    id: i / 2,
+                    start: size,
+                    len,
+                });
+            }
+            size += len;
+        }
+
+        Ok(Self { extents, size })
     }
 
-    fn known_solution_part1(&self) -> Option<String> {
-        Some(String::from("6448989155953"))
+    pub fn extents(&self) -> &[Extent] {
+        &self.extents
     }
 
-    fn part2(&mut self, input: &str) -> String {
-        let mut file_id = NonZeroU16::new(1).unwrap();
+    /// `(start, len)` of every run of free blocks, left to right.
+    pub fn free_spans(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let ends = self.extents.iter().map(|e| e.start).chain([self.size]);
+        let starts = [0]
+            .into_iter()
+            .chain(self.extents.iter().map(|e| e.start + e.len));
 
-        let mut arr = input
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as usize)
-            .enumerate()
-            .map(|(i, c)| {
-                (
-                    if i % 2 == 0 {
-                        let new = Space::File(file_id);
-                        file_id = file_id.checked_add(1).unwrap();
-                        new
-                    } else {
-                        Space::Empty
-                    },
-                    c,
-                )
-            })
-            .flat_map(|(file, c)| std::iter::repeat_n(file, c))
-            .collect_vec();
+        starts
+            .zip(ends)
+            .filter(|(start, end)| end > start)
+            .map(|(start, end)| (start, end - start))
+    }
 
-        let binding = arr
-            .iter()
-            .enumerate()
-            .rev()
-            .filter(|s| matches!(*s.1, Space::File(_)))
-            .chunk_by(|s| match *s.1 {
-                Space::File(c) => c,
-                _ => unreachable!(),
-            });
-
-        let chunks = binding
-            .into_iter()
-            .map(|(file_id, v)| (file_id, v.map(|(idx, space)| (idx, *space)).collect_vec()))
-            .collect_vec();
+    /// Moves single blocks from the end of the disk into the leftmost free
+    /// block until there are no gaps, splitting files as needed.
+    pub fn compact_blocks(&self) -> Self {
+        let mut files = self.extents.clone();
+        let mut moved = vec![];
+
+        'gaps: for (mut start, mut len) in self.free_spans().collect::<Vec<_>>() {
+            while len > 0 {
+                let Some(last) = files.last_mut() else {
+                    break 'gaps;
+                };
+                if last.start < start {
+                    break 'gaps;
+                }
 
-        for (file_id, file_chunk) in chunks {
-            // find first empty chunk of size file_chunk.len() - 1
+                let take = len.min(last.len);
+                moved.push(Extent {
+                    id: last.id,
+                    start,
+                    len: take,
+                });
 
-            let chunk_by = arr
-                .iter()
-                .enumerate()
-                .chunk_by(|(_, s)| **s == Space::Empty);
-
-            let indexes_to_swap = chunk_by
-                .into_iter()
-                .filter(|(is_empty, _)| *is_empty)
-                .map(|(_, v)| v.collect_vec())
-                .find(|v| v.len() >= file_chunk.len())
-                .into_iter()
-                .flat_map(|a| a.into_iter().map(|(index, _)| index).take(file_chunk.len()))
-                .collect_vec();
-
-            // if the index swap would result in a right shift, stop.
-            let Some(first_index_swap_destintaion) = indexes_to_swap.first() else {
+                last.len -= take;
+                if last.len == 0 {
+                    files.pop();
+                }
+                start += take;
+                len -= take;
+            }
+        }
+
+        files.extend(moved);
+        files.sort_by_key(|e| e.start);
+
+        Self {
+            extents: files,
+            size: self.size,
+        }
+    }
+
+    /// Moves every file, highest id first, into the leftmost free span it
+    /// fits in whole, if that is further left.
+    ///
+    /// Free spans are kept in one min-heap of starts per length, so finding
+    /// the span is a peek at nine heaps.
+    ///
+    /// Only correct on a map as [`parse`](DiskMap::parse) returns it, with one
+    /// extent per file laid out in id order; not on the output of a compaction.
+    /// Debug builds check this.
+    pub fn compact_files(&self) -> Self {
+        debug_assert!(
+            self.extents.windows(2).all(|pair| pair[0].id < pair[1].id),
+            "compact_files needs one extent per file, in id order"
+        );
+
+        let mut spans: [BinaryHeap<Reverse<(usize, usize)>>; MAX_SPAN + 1] = Default::default();
+        for (start, len) in self.free_spans() {
+            spans[len.min(MAX_SPAN)].push(Reverse((start, len)));
+        }
+
+        let mut files = self.extents.clone();
+        files.sort_by_key(|e| Reverse((e.id, e.start)));
+
+        for file in &mut files {
+            let leftmost = (file.len.min(MAX_SPAN)..=MAX_SPAN)
+                .filter_map(|bucket| spans[bucket].peek().map(|&Reverse(span)| (span, bucket)))
+                .filter(|&((start, len), _)| len >= file.len && start < file.start)
+                .min();
+            let Some(((start, len), bucket)) = leftmost else {
                 continue;
             };
 
-            if *first_index_swap_destintaion >= file_chunk[0].0 {
-                continue;
-            }
+            // the blocks the file leaves free are not added as a span: with
+            // files in id order they are right of every file still to move and
+            // could never be used
+            spans[bucket].pop();
+            file.start = start;
 
-            for index in indexes_to_swap {
-                arr[index] = Space::File(file_id);
+            // what is left of the span after the file can take another one
+            let rest = len - file.len;
+            if rest > 0 {
+                spans[rest.min(MAX_SPAN)].push(Reverse((start + file.len, rest)));
             }
+        }
 
-            for (index, _) in file_chunk {
-                arr[index] = Space::Empty;
-            }
+        files.sort_by_key(|e| e.start);
+
+        Self {
+            extents: files,
+            size: self.size,
         }
+    }
 
-        // calculate checksum
+    /// Sum of every block's position times its file id.
+    pub fn checksum(&self) -> usize {
+        self.extents
+            .iter()
+            .map(|e| e.id * (e.start * e.len + e.len * (e.len.saturating_sub(1)) / 2))
+            .sum()
+    }
 
-        arr.iter()
-            .enumerate()
-            .filter(|(_, s)| **s != Space::Empty)
-            .map(|(i, s)| match s {
-                Space::File(c) => (c.get() as usize - 1) * i,
-                _ => unreachable!(),
-            })
-            .sum::<usize>()
-            .to_string()
+    /// One character per block: `.` for free ones, the last digit of the
+    /// file id otherwise.
+    pub fn render(&self) -> String {
+        let mut blocks = vec!['.'; self.size];
+        for e in &self.extents {
+            let digit = char::from_digit((e.id % 10) as u32, 10).unwrap();
+            blocks[e.start..e.start + e.len].fill(digit);
+        }
+
+        blocks.into_iter().collect()
+    }
+}
+
+impl TrySolution for Day9 {
+    type Parsed = DiskMap;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        DiskMap::parse(input)
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
-        Some(String::from("6476642796832"))
+    fn try_part1(&mut self, disk: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(disk.compact_blocks().checksum().into())
+    }
+
+    fn try_part2(&mut self, disk: &Self::Parsed) -> Result<Answer, SolveError> {
+        Ok(disk.compact_files().checksum().into())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Part;
 
     #[test]
     fn test_part1() {
         let mut solution = Day9::new();
         assert_eq!(
-            solution.part1(r#"2333133121414131402"#),
-            String::from("1928")
+            solution.solve(Part::One, r#"2333133121414131402"#),
+            Ok(Answer::from(1928usize))
         );
     }
 
@@ -182,8 +227,125 @@ mod tests {
     fn test_part2() {
         let mut solution = Day9::new();
         assert_eq!(
-            solution.part2(r#"2333133121414131402"#),
-            String::from("2858")
+            solution.solve(Part::Two, r#"2333133121414131402"#),
+            Ok(Answer::from(2858usize))
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let disk = DiskMap::parse("2333133121414131402").unwrap();
+
+        assert_eq!(disk.render(), "00...111...2...333.44.5555.6666.777.888899");
+        assert_eq!(
+            disk.compact_blocks().render(),
+            "0099811188827773336446555566.............."
+        );
+        assert_eq!(
+            disk.compact_files().render(),
+            "00992111777.44.333....5555.6666.....8888.."
+        );
+        assert_eq!(disk.free_spans().next(), Some((2, 3)));
+
+        let err = DiskMap::parse("12x4").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(crate::InputLocation { line: 1, column: 3 })
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "one extent per file")]
+    fn test_compact_files_needs_a_parsed_map() {
+        // compacting blocks splits files over several extents
+        let disk = DiskMap::parse("2333133121414131402").unwrap();
+        disk.compact_blocks().compact_files();
+    }
+
+    /// One entry per block, moving files the slow way.
+    fn compact_files_naive(input: &str) -> usize {
+        let mut blocks = vec![];
+        for (i, c) in input.chars().enumerate() {
+            let file = (i % 2 == 0).then_some(i / 2);
+            blocks.extend(std::iter::repeat_n(file, c.to_digit(10).unwrap() as usize));
+        }
+
+        for id in (0..input.len().div_ceil(2)).rev() {
+            let Some(start) = blocks.iter().position(|&b| b == Some(id)) else {
+                continue;
+            };
+            let len = blocks[start..]
+                .iter()
+                .take_while(|&&b| b == Some(id))
+                .count();
+
+            let Some(free) = (0..start).find(|&i| blocks[i..i + len].iter().all(Option::is_none))
+            else {
+                continue;
+            };
+            blocks[start..start + len].fill(None);
+            blocks[free..free + len].fill(Some(id));
+        }
+
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(i, b)| i * b.unwrap_or(0))
+            .sum()
+    }
+
+    fn generate(len: usize, mut seed: u64) -> String {
+        (0..len)
+            .map(|_| {
+                seed = seed
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                char::from_digit((seed >> 33) as u32 % 10, 10).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_against_naive() {
+        for seed in 0..50 {
+            let input = generate(41, seed);
+            let disk = DiskMap::parse(&input).unwrap();
+            assert_eq!(
+                disk.compact_files().checksum(),
+                compact_files_naive(&input),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_big_disk() {
+        let input = generate(200_000, 7);
+        let disk = DiskMap::parse(&input).unwrap();
+
+        let files = disk.compact_files();
+        let blocks = disk.compact_blocks();
+
+        // every file is still whole, no further right and overlaps nothing
+        assert_eq!(files.extents().len(), disk.extents().len());
+        let mut before = disk.extents().to_vec();
+        let mut after = files.extents().to_vec();
+        before.sort_by_key(|e| e.id);
+        after.sort_by_key(|e| e.id);
+        for (before, after) in before.iter().zip(&after) {
+            assert_eq!((before.id, before.len), (after.id, after.len));
+            assert!(after.start <= before.start);
+        }
+        assert!(
+            files
+                .extents()
+                .windows(2)
+                .all(|pair| pair[0].start + pair[0].len <= pair[1].start)
         );
+
+        let used = |disk: &DiskMap| disk.extents().iter().map(|e| e.len).sum::<usize>();
+        assert_eq!(used(&blocks), used(&disk));
+        assert_eq!(blocks.free_spans().count(), 1);
     }
 }
@@ -44,6 +44,16 @@ impl QuadDirection {
         }
     }
 
+    pub fn rotate_left(self) -> QuadDirection {
+        match self {
+            Self::Up => Self::Left,
+            Self::Down => Self::Right,
+            Self::Left => Self::Down,
+            Self::Right => Self::Up,
+            Self::None => panic!("Cannot rotate None"),
+        }
+    }
+
     pub fn to_coord_offset(self) -> Coord {
         match self {
             Self::Up => Coord::new(-1, 0),
@@ -79,6 +89,85 @@ impl QuadDirection {
     }
 }
 
+/// Compass direction including diagonals.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub enum OctDirection {
+    Up,
+    UpRight,
+    Right,
+    DownRight,
+    Down,
+    DownLeft,
+    Left,
+    UpLeft,
+}
+
+impl OctDirection {
+    pub fn to_coord_offset(self) -> Coord {
+        match self {
+            Self::Up => Coord::new(-1, 0),
+            Self::UpRight => Coord::new(-1, 1),
+            Self::Right => Coord::new(0, 1),
+            Self::DownRight => Coord::new(1, 1),
+            Self::Down => Coord::new(1, 0),
+            Self::DownLeft => Coord::new(1, -1),
+            Self::Left => Coord::new(0, -1),
+            Self::UpLeft => Coord::new(-1, -1),
+        }
+    }
+
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Up => Self::Down,
+            Self::UpRight => Self::DownLeft,
+            Self::Right => Self::Left,
+            Self::DownRight => Self::UpLeft,
+            Self::Down => Self::Up,
+            Self::DownLeft => Self::UpRight,
+            Self::Left => Self::Right,
+            Self::UpLeft => Self::DownRight,
+        }
+    }
+
+    /// All eight directions, clockwise from up.
+    pub fn get_all_directions() -> &'static [Self] {
+        &[
+            Self::Up,
+            Self::UpRight,
+            Self::Right,
+            Self::DownRight,
+            Self::Down,
+            Self::DownLeft,
+            Self::Left,
+            Self::UpLeft,
+        ]
+    }
+
+    pub fn get_diagonals() -> &'static [Self] {
+        &[Self::UpRight, Self::DownRight, Self::DownLeft, Self::UpLeft]
+    }
+}
+
+impl From<QuadDirection> for OctDirection {
+    fn from(dir: QuadDirection) -> Self {
+        match dir {
+            QuadDirection::Up => Self::Up,
+            QuadDirection::Down => Self::Down,
+            QuadDirection::Left => Self::Left,
+            QuadDirection::Right => Self::Right,
+            QuadDirection::None => panic!("None has no OctDirection"),
+        }
+    }
+}
+
+impl std::ops::Add<OctDirection> for Coord {
+    type Output = Coord;
+
+    fn add(self, other: OctDirection) -> Self::Output {
+        self + other.to_coord_offset()
+    }
+}
+
 impl std::ops::Add<Coord> for QuadDirection {
     type Output = Coord;
 
@@ -1,6 +1,9 @@
 use itertools::Itertools;
 
-use crate::direction::QuadDirection;
+use crate::{
+    SolveError,
+    direction::{OctDirection, QuadDirection},
+};
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct Coord {
@@ -31,9 +34,9 @@ impl Coord {
 
     #[allow(dead_code)]
     pub fn from_enumerated_grid<T>(grid: &Grid<T>, index: usize) -> Self {
-        let i = i16::try_from(index).unwrap();
+        let width = grid.width as usize;
 
-        Self::new(i / grid.width, i % grid.width)
+        Self::new_usize(index / width, index % width)
     }
 
     pub fn in_bounds(&self, width: i16, height: i16) -> bool {
@@ -177,6 +180,57 @@ impl<T> Grid<T> {
         }
     }
 
+    /// Parses one cell per byte, one row per line.
+    ///
+    /// Accepts `\n` and `\r\n` line endings and trailing newlines, but every
+    /// row has to be as wide as the first. Rows and columns in errors are
+    /// 1-based and relative to `input`.
+    pub fn try_parse<E: std::fmt::Display>(
+        input: &str,
+        char_to_t: impl Fn(u8) -> Result<T, E>,
+    ) -> Result<Self, GridError> {
+        let input = input.trim_end_matches(['\r', '\n']);
+        if input.is_empty() {
+            return Err(GridError::Empty);
+        }
+
+        let mut data = Vec::with_capacity(input.len());
+        let mut width = 0;
+        let mut height = 0;
+
+        for (i, line) in input.lines().enumerate() {
+            let row = i + 1;
+            if row == 1 {
+                width = line.len();
+            } else if line.len() != width {
+                return Err(GridError::Ragged {
+                    row,
+                    width: line.len(),
+                    expected: width,
+                });
+            }
+
+            for (j, c) in line.bytes().enumerate() {
+                let t = char_to_t(c).map_err(|err| GridError::Cell {
+                    row,
+                    column: j + 1,
+                    message: err.to_string(),
+                })?;
+                data.push(t);
+            }
+
+            height = row;
+        }
+
+        let too_big = |_| GridError::TooBig { width, height };
+
+        Ok(Self {
+            width: i16::try_from(width).map_err(too_big)?,
+            height: i16::try_from(height).map_err(too_big)?,
+            data,
+        })
+    }
+
     pub fn new_blank(width: i16, height: i16, value: T) -> Self
     where
         T: Clone,
@@ -198,11 +252,14 @@ impl<T> Grid<T> {
     }
 
     pub fn iter_with_coords(&self) -> impl DoubleEndedIterator<Item = (Coord, &T)> {
-        self.data.iter().enumerate().map(|(i, t)| {
-            let i = i16::try_from(i).unwrap();
-
-            (Coord::new(i / self.width, i % self.width), t)
-        })
+        // the flat index can be past i16::MAX even though rows and columns
+        // never are, so split it up before narrowing
+        let width = self.width as usize;
+
+        self.data
+            .iter()
+            .enumerate()
+            .map(move |(i, t)| (Coord::new((i / width) as i16, (i % width) as i16), t))
     }
 
     pub fn iter_lines(&self) -> impl DoubleEndedIterator<Item = &[T]> {
@@ -245,6 +302,30 @@ impl<T> Grid<T> {
 
         Some(&self[coord])
     }
+
+    /// The cells one step from `coord` in each of `dirs` that are on the grid.
+    pub fn neighbors_with<D>(
+        &self,
+        coord: Coord,
+        dirs: impl IntoIterator<Item = D>,
+    ) -> impl Iterator<Item = (Coord, &T)>
+    where
+        Coord: std::ops::Add<D, Output = Coord>,
+    {
+        dirs.into_iter()
+            .map(move |dir| coord + dir)
+            .filter_map(|neighbor| self.get(neighbor).map(|t| (neighbor, t)))
+    }
+
+    /// Orthogonal neighbors of `coord`.
+    pub fn neighbors4(&self, coord: Coord) -> impl Iterator<Item = (Coord, &T)> {
+        self.neighbors_with(coord, QuadDirection::get_all_directions().iter().copied())
+    }
+
+    /// Orthogonal and diagonal neighbors of `coord`.
+    pub fn neighbors8(&self, coord: Coord) -> impl Iterator<Item = (Coord, &T)> {
+        self.neighbors_with(coord, OctDirection::get_all_directions().iter().copied())
+    }
 }
 
 impl<T: std::fmt::Display> Grid<T> {
@@ -279,19 +360,86 @@ impl<T: std::fmt::Display> Grid<T> {
     }
 
     pub fn pretty_print_bolded_coords(&self, coords: &[Coord]) {
-        for (i, line) in self.iter_lines().enumerate() {
-            println!(
-                "{}",
+        for line in self.pretty_print_bolded_coords_into_rows(coords) {
+            println!("{line}");
+        }
+    }
+
+    pub fn pretty_print_bolded_coords_into_rows(&self, coords: &[Coord]) -> Vec<String> {
+        self.iter_lines()
+            .enumerate()
+            .map(|(i, line)| {
                 line.iter()
                     .enumerate()
-                    .map(|(j, t)| if coords.contains(&Coord::new_usize(i, j)) {
-                        use colored::*;
-                        format!("{}", t).on_bright_red().black().to_string()
-                    } else {
-                        t.to_string()
+                    .map(|(j, t)| {
+                        if coords.contains(&Coord::new_usize(i, j)) {
+                            use colored::*;
+                            format!("{}", t).on_bright_red().black().to_string()
+                        } else {
+                            t.to_string()
+                        }
                     })
                     .join("")
-            );
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GridError {
+    Empty,
+    Ragged {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+    Cell {
+        row: usize,
+        column: usize,
+        message: String,
+    },
+    TooBig {
+        width: usize,
+        height: usize,
+    },
+}
+
+impl std::fmt::Display for GridError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GridError::Empty => write!(f, "grid is empty"),
+            GridError::Ragged {
+                row,
+                width,
+                expected,
+            } => write!(f, "row {row} is {width} wide, expected {expected}"),
+            GridError::Cell {
+                row,
+                column,
+                message,
+            } => write!(f, "row {row} column {column}: {message}"),
+            GridError::TooBig { width, height } => {
+                write!(
+                    f,
+                    "grid of {width}x{height} does not fit in i16 coordinates"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl From<GridError> for SolveError {
+    fn from(err: GridError) -> Self {
+        match err {
+            GridError::Ragged { row, .. } => SolveError::at(row, 1, err.to_string()),
+            GridError::Cell {
+                row,
+                column,
+                message,
+            } => SolveError::at(row, column, message),
+            GridError::Empty | GridError::TooBig { .. } => SolveError::new(err.to_string()),
         }
     }
 }
@@ -309,3 +457,93 @@ impl<T> std::ops::IndexMut<Coord> for Grid<T> {
         &mut self.data[index.col as usize + index.row as usize * self.width as usize]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digit(c: u8) -> Result<u8, String> {
+        match c {
+            b'0'..=b'9' => Ok(c - b'0'),
+            _ => Err(format!("{:?} is not a digit", c as char)),
+        }
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let grid = Grid::try_parse("012\r\n345\r\n", digit).unwrap();
+
+        assert_eq!((grid.width, grid.height), (3, 2));
+        assert_eq!(grid[Coord::new(1, 2)], 5);
+        assert_eq!(grid.pretty_print_into_rows(), vec!["012", "345"]);
+
+        let grid = Grid::try_parse("01\n23\n45\n\n", digit).unwrap();
+        assert_eq!((grid.width, grid.height), (2, 3));
+    }
+
+    #[test]
+    fn test_more_cells_than_i16() {
+        let input = vec!["7".repeat(200); 200].join("\n");
+        let grid = Grid::try_parse(&input, digit).unwrap();
+
+        assert_eq!((grid.width, grid.height), (200, 200));
+        assert_eq!(
+            grid.iter_with_coords().last(),
+            Some((Coord::new(199, 199), &7))
+        );
+        assert_eq!(
+            Coord::from_enumerated_grid(&grid, 40_000 - 1),
+            Coord::new(199, 199)
+        );
+        assert_eq!(grid[Coord::new(199, 199)], 7);
+    }
+
+    #[test]
+    fn test_neighbors() {
+        let grid = Grid::try_parse("012\n345\n678", digit).unwrap();
+        let values = |neighbors: Vec<(Coord, &u8)>| {
+            let mut values = neighbors.into_iter().map(|(_, v)| *v).collect::<Vec<_>>();
+            values.sort();
+            values
+        };
+
+        assert_eq!(
+            values(grid.neighbors4(Coord::new(0, 0)).collect()),
+            vec![1, 3]
+        );
+        assert_eq!(
+            values(grid.neighbors4(Coord::new(1, 1)).collect()),
+            vec![1, 3, 5, 7]
+        );
+        assert_eq!(
+            values(grid.neighbors8(Coord::new(0, 2)).collect()),
+            vec![1, 4, 5]
+        );
+        assert_eq!(grid.neighbors8(Coord::new(1, 1)).count(), 8);
+        assert_eq!(
+            grid.neighbors_with(Coord::new(2, 2), [OctDirection::UpLeft, OctDirection::Down])
+                .collect::<Vec<_>>(),
+            vec![(Coord::new(1, 1), &4)]
+        );
+    }
+
+    #[test]
+    fn test_try_parse_errors() {
+        assert_eq!(
+            Grid::try_parse("012\n345\n67", digit).unwrap_err(),
+            GridError::Ragged {
+                row: 3,
+                width: 2,
+                expected: 3
+            }
+        );
+        assert_eq!(Grid::try_parse("\n", digit).unwrap_err(), GridError::Empty);
+
+        let err = SolveError::from(Grid::try_parse("012\n3x5", digit).unwrap_err());
+        assert_eq!(
+            err.location,
+            Some(crate::InputLocation { line: 2, column: 2 })
+        );
+        assert_eq!(err.message, "'x' is not a digit");
+    }
+}
@@ -0,0 +1,315 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    Part,
+    answers::{ANSWERS_FILE, AnswerError, AnswerRegistry},
+};
+
+/// Name of the input each day is solved against unless told otherwise.
+pub const DEFAULT_INPUT: &str = "input";
+
+/// Directory containing `{day}_{name}.txt` files, overridable by `AOC_INPUT_DIR`.
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+pub trait InputProvider {
+    fn get(&self, day: u8, name: &str) -> Result<String, InputError>;
+
+    /// Every input name known for `day`, sorted.
+    fn names(&self, day: u8) -> Vec<String>;
+
+    /// Known answers for the inputs this provider serves.
+    fn answers(&self) -> Result<AnswerRegistry, AnswerError> {
+        Ok(AnswerRegistry::new())
+    }
+}
+
+#[derive(Debug)]
+pub enum InputError {
+    Missing {
+        day: u8,
+        name: String,
+        path: Option<PathBuf>,
+    },
+    Empty {
+        day: u8,
+        name: String,
+    },
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl std::fmt::Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InputError::Missing {
+                day,
+                name,
+                path: Some(path),
+            } => write!(
+                f,
+                "input {name:?} for day {day} not found at {}",
+                path.display()
+            ),
+            InputError::Missing {
+                day,
+                name,
+                path: None,
+            } => write!(f, "input {name:?} for day {day} not found"),
+            InputError::Empty { day, name } => write!(f, "input {name:?} for day {day} is empty"),
+            InputError::Io { path, source } => write!(f, "reading {}: {source}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for InputError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InputError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+fn non_empty(day: u8, name: &str, contents: String) -> Result<String, InputError> {
+    if contents.trim().is_empty() {
+        return Err(InputError::Empty {
+            day,
+            name: name.to_string(),
+        });
+    }
+
+    Ok(contents)
+}
+
+/// Reads `{dir}/{day}_{name}.txt`, so `inputs/3_input.txt` is day 3's `input`
+/// and `inputs/3_example.txt` its `example`.
+#[derive(Debug, Clone)]
+pub struct FsInputProvider {
+    dir: PathBuf,
+}
+
+impl FsInputProvider {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, day: u8, name: &str) -> PathBuf {
+        self.dir.join(format!("{day}_{name}.txt"))
+    }
+}
+
+impl Default for FsInputProvider {
+    /// The crate's own `inputs` directory, wherever the binary is run from.
+    fn default() -> Self {
+        Self::new(concat!(env!("CARGO_MANIFEST_DIR"), "/inputs"))
+    }
+}
+
+impl InputProvider for FsInputProvider {
+    fn get(&self, day: u8, name: &str) -> Result<String, InputError> {
+        let path = self.path(day, name);
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => non_empty(day, name, contents),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Err(InputError::Missing {
+                day,
+                name: name.to_string(),
+                path: Some(path),
+            }),
+            Err(source) => Err(InputError::Io { path, source }),
+        }
+    }
+
+    fn names(&self, day: u8) -> Vec<String> {
+        let prefix = format!("{day}_");
+
+        let Ok(entries) = std::fs::read_dir(&self.dir) else {
+            return vec![];
+        };
+
+        let mut names = entries
+            .flatten()
+            .filter_map(|entry| {
+                let file_name = entry.file_name();
+                let name = file_name
+                    .to_str()?
+                    .strip_prefix(&prefix)?
+                    .strip_suffix(".txt")?;
+                Some(name.to_string())
+            })
+            .collect::<Vec<_>>();
+
+        names.sort();
+        names
+    }
+
+    fn answers(&self) -> Result<AnswerRegistry, AnswerError> {
+        AnswerRegistry::load(&self.dir.join(ANSWERS_FILE))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MemoryInputProvider {
+    inputs: BTreeMap<(u8, String), String>,
+    answers: AnswerRegistry,
+}
+
+impl MemoryInputProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, day: u8, name: &str, contents: impl Into<String>) -> Self {
+        self.insert(day, name, contents);
+        self
+    }
+
+    pub fn insert(&mut self, day: u8, name: &str, contents: impl Into<String>) {
+        self.inputs.insert((day, name.to_string()), contents.into());
+    }
+
+    pub fn with_answer(mut self, day: u8, name: &str, part: Part, answer: &str) -> Self {
+        self.answers.insert(day, name, part, answer);
+        self
+    }
+}
+
+impl InputProvider for MemoryInputProvider {
+    fn get(&self, day: u8, name: &str) -> Result<String, InputError> {
+        let contents =
+            self.inputs
+                .get(&(day, name.to_string()))
+                .ok_or_else(|| InputError::Missing {
+                    day,
+                    name: name.to_string(),
+                    path: None,
+                })?;
+
+        non_empty(day, name, contents.clone())
+    }
+
+    fn names(&self, day: u8) -> Vec<String> {
+        self.inputs
+            .keys()
+            .filter(|(d, _)| *d == day)
+            .map(|(_, name)| name.clone())
+            .collect()
+    }
+
+    fn answers(&self) -> Result<AnswerRegistry, AnswerError> {
+        Ok(self.answers.clone())
+    }
+}
+
+/// Filesystem provider configured through the environment.
+///
+/// The directory comes from `AOC_INPUT_DIR` (falling back to the crate's
+/// `inputs`), and a single input can be pointed at any file with
+/// `AOC_INPUT_{day}_{NAME}`, e.g. `AOC_INPUT_6_ALICE=/tmp/alice6.txt`.
+#[derive(Debug, Clone)]
+pub struct EnvInputProvider {
+    fs: FsInputProvider,
+}
+
+impl EnvInputProvider {
+    pub fn new() -> Self {
+        let fs = std::env::var_os(INPUT_DIR_VAR)
+            .map(FsInputProvider::new)
+            .unwrap_or_default();
+
+        Self { fs }
+    }
+
+    fn override_var(day: u8, name: &str) -> String {
+        format!("AOC_INPUT_{day}_{}", name.to_uppercase())
+    }
+}
+
+impl Default for EnvInputProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InputProvider for EnvInputProvider {
+    fn get(&self, day: u8, name: &str) -> Result<String, InputError> {
+        let Some(path) = std::env::var_os(Self::override_var(day, name)).map(PathBuf::from) else {
+            return self.fs.get(day, name);
+        };
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => non_empty(day, name, contents),
+            Err(source) => Err(InputError::Io { path, source }),
+        }
+    }
+
+    fn names(&self, day: u8) -> Vec<String> {
+        let prefix = format!("AOC_INPUT_{day}_");
+
+        let mut names = self.fs.names(day);
+        names.extend(
+            std::env::vars_os()
+                .filter_map(|(key, _)| Some(key.to_str()?.strip_prefix(&prefix)?.to_lowercase())),
+        );
+
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    fn answers(&self) -> Result<AnswerRegistry, AnswerError> {
+        self.fs.answers()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_provider() {
+        let provider = MemoryInputProvider::new()
+            .with(3, "example", "mul(2,4)")
+            .with(3, "alice", "\n")
+            .with(4, "example", "XMAS");
+
+        assert_eq!(provider.get(3, "example").unwrap(), "mul(2,4)");
+        assert_eq!(provider.names(3), vec!["alice", "example"]);
+        assert!(matches!(
+            provider.get(3, "alice"),
+            Err(InputError::Empty { day: 3, .. })
+        ));
+        assert!(matches!(
+            provider.get(3, "bob"),
+            Err(InputError::Missing { day: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn test_fs_provider() {
+        let provider = FsInputProvider::default();
+
+        assert!(provider.get(1, DEFAULT_INPUT).is_ok());
+        assert!(provider.names(1).contains(&DEFAULT_INPUT.to_string()));
+        assert!(
+            provider
+                .answers()
+                .unwrap()
+                .get(1, DEFAULT_INPUT, Part::One)
+                .is_some()
+        );
+        assert!(matches!(
+            provider.get(1, "nobody"),
+            Err(InputError::Missing { path: Some(_), .. })
+        ));
+    }
+}
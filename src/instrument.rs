@@ -0,0 +1,280 @@
+//! Named iteration counters with optional budgets.
+//!
+//! Counters belong to a [`Context`], which the runner creates for every part
+//! it solves, so parallel runs never see each other's counts or budgets. A
+//! solution calls [`tick`] in its hot loops, which counts into the context
+//! [entered](Context::enter) on the current thread; when the counter goes
+//! over a budget set with [`Context::set_budget`] the tick returns a
+//! [`BudgetExceeded`] naming the counter and the line that tripped it, which
+//! `?` turns into a [`SolveError`].
+//!
+//! Rayon workers have not entered the context, so parallel code takes it with
+//! [`current`] first and ticks it directly. Outside of any context ticks go
+//! to a process wide one nobody reads budgets from.
+
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    panic::Location,
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use dashmap::DashMap;
+
+use crate::SolveError;
+
+struct Counter {
+    count: AtomicU64,
+    budget: AtomicU64,
+    /// Count at which the budget runs out, `u64::MAX` when there is none.
+    limit: AtomicU64,
+}
+
+impl Counter {
+    fn new() -> Self {
+        Self {
+            count: AtomicU64::new(0),
+            budget: AtomicU64::new(0),
+            limit: AtomicU64::new(u64::MAX),
+        }
+    }
+}
+
+/// A set of counters and their budgets, shared by every clone.
+#[derive(Clone, Default)]
+pub struct Context {
+    counters: Arc<DashMap<String, Counter>>,
+}
+
+static DETACHED: LazyLock<Context> = LazyLock::new(Context::new);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Context>> = const { RefCell::new(None) };
+}
+
+/// A counter went over its budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BudgetExceeded {
+    pub counter: String,
+    pub budget: u64,
+    pub location: &'static Location<'static>,
+}
+
+impl std::fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} went over its budget of {} at {}",
+            self.counter, self.budget, self.location
+        )
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+impl From<BudgetExceeded> for SolveError {
+    fn from(err: BudgetExceeded) -> Self {
+        SolveError::new(err.to_string())
+    }
+}
+
+/// Puts the previous context back when [`Context::enter`] returns or unwinds.
+struct Restore(Option<Context>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f` with [`tick`] counting into this context on this thread.
+    pub fn enter<R>(&self, f: impl FnOnce() -> R) -> R {
+        let previous = CURRENT.with(|current| current.borrow_mut().replace(self.clone()));
+        let _restore = Restore(previous);
+
+        f()
+    }
+
+    /// Adds `n` to `counter`, failing once it is over its budget.
+    #[track_caller]
+    pub fn tick_by(&self, counter: &str, n: u64) -> Result<(), BudgetExceeded> {
+        self.tick_at(counter, n, Location::caller())
+    }
+
+    fn tick_at(
+        &self,
+        counter: &str,
+        n: u64,
+        location: &'static Location<'static>,
+    ) -> Result<(), BudgetExceeded> {
+        let check = |entry: &Counter| {
+            let count = entry.count.fetch_add(n, Ordering::Relaxed) + n;
+
+            if count > entry.limit.load(Ordering::Relaxed) {
+                Err(BudgetExceeded {
+                    counter: counter.to_string(),
+                    budget: entry.budget.load(Ordering::Relaxed),
+                    location,
+                })
+            } else {
+                Ok(())
+            }
+        };
+
+        // only take the write lock the first time a counter is seen
+        match self.counters.get(counter) {
+            Some(entry) => check(&entry),
+            None => check(
+                &self
+                    .counters
+                    .entry(counter.to_string())
+                    .or_insert_with(Counter::new),
+            ),
+        }
+    }
+
+    /// Current value of `counter`.
+    pub fn count(&self, counter: &str) -> u64 {
+        self.counters
+            .get(counter)
+            .map_or(0, |entry| entry.count.load(Ordering::Relaxed))
+    }
+
+    /// Makes ticks fail once `counter` has gone up by more than `budget`
+    /// from where it is now.
+    pub fn set_budget(&self, counter: &str, budget: u64) {
+        let entry = self
+            .counters
+            .entry(counter.to_string())
+            .or_insert_with(Counter::new);
+
+        let count = entry.count.load(Ordering::Relaxed);
+        entry.budget.store(budget, Ordering::Relaxed);
+        entry
+            .limit
+            .store(count.saturating_add(budget), Ordering::Relaxed);
+    }
+
+    pub fn clear_budget(&self, counter: &str) {
+        if let Some(entry) = self.counters.get(counter) {
+            entry.limit.store(u64::MAX, Ordering::Relaxed);
+        }
+    }
+
+    /// Every counter that has been ticked so far.
+    pub fn snapshot(&self) -> BTreeMap<String, u64> {
+        self.counters
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.count.load(Ordering::Relaxed)))
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+}
+
+/// The context entered on this thread, or the detached one.
+pub fn current() -> Context {
+    CURRENT.with(|current| current.borrow().clone().unwrap_or_else(|| DETACHED.clone()))
+}
+
+/// Adds one to `counter`.
+#[track_caller]
+pub fn tick(counter: &str) -> Result<(), BudgetExceeded> {
+    tick_by(counter, 1)
+}
+
+/// Adds `n` to `counter` in the current context, failing once it is over
+/// its budget.
+#[track_caller]
+pub fn tick_by(counter: &str, n: u64) -> Result<(), BudgetExceeded> {
+    let location = Location::caller();
+
+    CURRENT.with(|current| match &*current.borrow() {
+        Some(context) => context.tick_at(counter, n, location),
+        None => DETACHED.tick_at(counter, n, location),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick() {
+        let context = Context::new();
+        context.enter(|| {
+            for _ in 0..5 {
+                tick("test.tick").unwrap();
+            }
+            tick_by("test.tick", 10).unwrap();
+        });
+
+        assert_eq!(context.count("test.tick"), 15);
+        assert_eq!(context.snapshot().get("test.tick"), Some(&15));
+        assert_eq!(context.count("test.never"), 0);
+
+        // nothing leaks into another context or out of this one
+        let other = Context::new();
+        other.enter(|| tick("test.tick").unwrap());
+        tick("test.tick").unwrap();
+        assert_eq!(other.count("test.tick"), 1);
+        assert_eq!(context.count("test.tick"), 15);
+    }
+
+    #[test]
+    fn test_budget() {
+        let context = Context::new();
+        context.tick_by("test.budget", 100).unwrap();
+        context.set_budget("test.budget", 3);
+
+        context.enter(|| {
+            for _ in 0..3 {
+                tick("test.budget").unwrap();
+            }
+            let line = line!() + 1;
+            let err = tick("test.budget").unwrap_err();
+
+            assert_eq!(err.counter, "test.budget");
+            assert_eq!(err.budget, 3);
+            assert_eq!(err.location.file(), file!());
+            assert_eq!(err.location.line(), line);
+
+            let err = SolveError::from(err);
+            assert!(
+                err.message
+                    .starts_with("test.budget went over its budget of 3 at")
+            );
+        });
+
+        // budgets belong to their context
+        assert!(Context::new().tick_by("test.budget", 10).is_ok());
+
+        context.clear_budget("test.budget");
+        assert!(context.enter(|| tick("test.budget")).is_ok());
+    }
+
+    #[test]
+    fn test_threads() {
+        let context = Context::new();
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                let context = context.clone();
+                s.spawn(move || {
+                    for _ in 0..1000 {
+                        context.tick_by("test.threads", 1).unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(context.count("test.threads"), 4000);
+    }
+}
@@ -0,0 +1,149 @@
+//! Invariants checked after every step of a grid simulation.
+//!
+//! A day registers named predicates over its grid with [`Invariants::with`]
+//! and hands the grid to [`Invariants::check`] after each step. The first
+//! predicate that fails comes back as a [`Violation`] holding the step, its
+//! move and the grid before and after it side by side.
+
+use std::fmt::Display;
+
+use crate::{SolveError, grid::Grid};
+
+type Predicate<T> = Box<dyn Fn(&Grid<T>) -> Result<(), String>>;
+
+pub struct Invariants<T> {
+    predicates: Vec<(&'static str, Predicate<T>)>,
+    previous: Option<Grid<T>>,
+    step: usize,
+}
+
+impl<T> Default for Invariants<T> {
+    fn default() -> Self {
+        Self {
+            predicates: vec![],
+            previous: None,
+            step: 0,
+        }
+    }
+}
+
+impl<T: Clone + Display> Invariants<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a predicate; it returns `Err` with a description of what is
+    /// wrong when the grid breaks it.
+    pub fn with(
+        mut self,
+        name: &'static str,
+        predicate: impl Fn(&Grid<T>) -> Result<(), String> + 'static,
+    ) -> Self {
+        self.predicates.push((name, Box::new(predicate)));
+        self
+    }
+
+    /// Checks `grid` as it is after the next step, `action` being whatever
+    /// the simulation did in it.
+    pub fn check(&mut self, action: impl Display, grid: &Grid<T>) -> Result<(), Violation> {
+        let step = self.step;
+        self.step += 1;
+
+        let failed = self
+            .predicates
+            .iter()
+            .find_map(|(name, predicate)| predicate(grid).err().map(|message| (*name, message)));
+
+        if let Some((invariant, message)) = failed {
+            return Err(Violation {
+                invariant,
+                step,
+                action: action.to_string(),
+                message,
+                diff: render_diff(self.previous.as_ref(), grid),
+            });
+        }
+
+        self.previous = Some(grid.clone());
+
+        Ok(())
+    }
+}
+
+/// Renders `before` and `after` next to each other, marking changed rows.
+fn render_diff<T: Display>(before: Option<&Grid<T>>, after: &Grid<T>) -> Vec<String> {
+    let after = after.pretty_print_into_rows();
+    let Some(before) = before.map(Grid::pretty_print_into_rows) else {
+        return after;
+    };
+
+    let width = before.first().map_or(0, String::len);
+
+    before
+        .iter()
+        .zip(&after)
+        .map(|(before, after)| {
+            let marker = if before == after { ' ' } else { '*' };
+            format!("{before:<width$}   {after} {marker}")
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub invariant: &'static str,
+    /// 0 is the starting grid, 1 the grid after the first step.
+    pub step: usize,
+    pub action: String,
+    pub message: String,
+    /// The grid before and after the step, changed rows marked with `*`.
+    pub diff: Vec<String>,
+}
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "invariant {:?} broken at step {} ({}): {}",
+            self.invariant, self.step, self.action, self.message
+        )?;
+        write!(f, "{}", self.diff.join("\n"))
+    }
+}
+
+impl std::error::Error for Violation {}
+
+impl From<Violation> for SolveError {
+    fn from(violation: Violation) -> Self {
+        SolveError::new(violation.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_x(grid: &Grid<char>) -> Result<(), String> {
+        match grid.iter_with_coords().find(|(_, c)| **c == 'x') {
+            Some((coord, _)) => Err(format!("x at {coord}")),
+            None => Ok(()),
+        }
+    }
+
+    #[test]
+    fn test_violation() {
+        let mut invariants = Invariants::new().with("no x", no_x);
+
+        let mut grid = Grid::new("ab\ncd", |c| c as char);
+        assert!(invariants.check("start", &grid).is_ok());
+
+        grid[crate::grid::Coord::new(1, 0)] = 'x';
+        let violation = invariants.check("put x", &grid).unwrap_err();
+
+        assert_eq!(violation.invariant, "no x");
+        assert_eq!(violation.step, 1);
+        assert_eq!(violation.action, "put x");
+        assert_eq!(violation.message, "x at (1, 0)");
+        assert_eq!(violation.diff, vec!["ab   ab  ", "cd   xd *"]);
+    }
+}
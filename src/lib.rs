@@ -1,3 +1,12 @@
+use std::{
+    any::Any,
+    panic::{AssertUnwindSafe, catch_unwind},
+};
+
+use input::InputProvider;
+
+pub type BigUint = bnum::types::U1024;
+
 pub trait Solution {
     fn new() -> Self
     where
@@ -5,16 +14,372 @@ pub trait Solution {
 
     fn part1(&mut self, input: &str) -> String;
     fn part2(&mut self, input: &str) -> String;
+}
 
-    fn known_solution_part1(&self) -> Option<String> {
-        None
+/// Fallible counterpart of [`Solution`] with a separate parse step.
+///
+/// The input is parsed once into [`TrySolution::Parsed`] and both parts work
+/// from that, so parsing can be timed and benchmarked on its own. Every
+/// [`Solution`] gets this for free through the blanket impl below, with the
+/// raw input as its parsed form and no parameters.
+pub trait TrySolution {
+    type Parsed;
+    /// Settings that differ between the example and the real input, `()`
+    /// for days without any.
+    type Params: params::Params;
+
+    /// The parameters currently in use.
+    fn params(&self) -> Self::Params {
+        Self::Params::default()
+    }
+
+    fn set_params(&mut self, _params: Self::Params) {}
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError>
+    where
+        Self: Sized;
+
+    fn try_part1(&mut self, parsed: &Self::Parsed) -> Result<Answer, SolveError>;
+    fn try_part2(&mut self, parsed: &Self::Parsed) -> Result<Answer, SolveError>;
+
+    fn try_part(&mut self, part: Part, parsed: &Self::Parsed) -> Result<Answer, SolveError> {
+        match part {
+            Part::One => self.try_part1(parsed),
+            Part::Two => self.try_part2(parsed),
+        }
+        .map_err(|err| err.with_part(part))
     }
 
-    fn known_solution_part2(&self) -> Option<String> {
+    /// A picture the last part drew next to its answer, for days that can
+    /// show their work. Taking it leaves `None` behind.
+    fn take_render(&mut self) -> Option<Vec<String>> {
         None
     }
+
+    /// Parses `input` and solves `part` of it in one go.
+    fn solve(&mut self, part: Part, input: &str) -> Result<Answer, SolveError>
+    where
+        Self: Sized,
+    {
+        let parsed = Self::parse(input)?;
+        self.try_part(part, &parsed)
+    }
 }
 
+impl<S: Solution> TrySolution for S {
+    type Parsed = String;
+    type Params = ();
+
+    fn parse(input: &str) -> Result<Self::Parsed, SolveError> {
+        Ok(input.to_string())
+    }
+
+    fn try_part1(&mut self, input: &String) -> Result<Answer, SolveError> {
+        Ok(Answer::Text(self.part1(input)))
+    }
+
+    fn try_part2(&mut self, input: &String) -> Result<Answer, SolveError> {
+        Ok(Answer::Text(self.part2(input)))
+    }
+}
+
+/// Object safe form of [`TrySolution`], as handed out by [`get_solution`].
+///
+/// The parsed input travels as `dyn Any`, and a panicking parse or part comes
+/// back as a [`SolveError`] instead of taking the whole process down.
+pub trait DynSolution {
+    fn parse_any(&self, input: &str) -> Result<Box<dyn Any>, SolveError>;
+    fn run_parsed(&mut self, part: Part, parsed: &dyn Any) -> Result<Answer, SolveError>;
+
+    /// Overrides parameters by name, see [`params`](mod@params).
+    fn configure(&mut self, overrides: &[(String, String)]) -> Result<(), SolveError>;
+
+    /// Names of the parameters [`configure`](DynSolution::configure) takes.
+    fn param_names(&self) -> &'static [&'static str];
+
+    /// See [`TrySolution::take_render`].
+    fn take_render(&mut self) -> Option<Vec<String>>;
+
+    fn run(&mut self, part: Part, input: &str) -> Result<Answer, SolveError> {
+        let parsed = self.parse_any(input)?;
+        self.run_parsed(part, parsed.as_ref())
+    }
+}
+
+impl<S> DynSolution for S
+where
+    S: TrySolution,
+    S::Parsed: 'static,
+{
+    fn parse_any(&self, input: &str) -> Result<Box<dyn Any>, SolveError> {
+        catch_unwind(|| S::parse(input))
+            .unwrap_or_else(|payload| Err(SolveError::from_panic(payload)))
+            .map(|parsed| Box::new(parsed) as Box<dyn Any>)
+    }
+
+    fn run_parsed(&mut self, part: Part, parsed: &dyn Any) -> Result<Answer, SolveError> {
+        let parsed = parsed
+            .downcast_ref::<S::Parsed>()
+            .ok_or_else(|| SolveError::new("parsed input belongs to another day"))?;
+
+        catch_unwind(AssertUnwindSafe(|| self.try_part(part, parsed)))
+            .unwrap_or_else(|payload| Err(SolveError::from_panic(payload).with_part(part)))
+    }
+
+    fn configure(&mut self, overrides: &[(String, String)]) -> Result<(), SolveError> {
+        use params::Params;
+
+        let mut current = self.params();
+        for (name, value) in overrides {
+            current.set(name, value)?;
+        }
+        self.set_params(current);
+
+        Ok(())
+    }
+
+    fn param_names(&self) -> &'static [&'static str] {
+        <S::Params as params::Params>::NAMES
+    }
+
+    fn take_render(&mut self) -> Option<Vec<String>> {
+        TrySolution::take_render(self)
+    }
+}
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(into = "u8", try_from = "u8")]
+pub enum Part {
+    One,
+    Two,
+}
+
+impl Part {
+    pub fn all() -> &'static [Self] {
+        &[Self::One, Self::Two]
+    }
+}
+
+impl From<Part> for u8 {
+    fn from(part: Part) -> Self {
+        match part {
+            Part::One => 1,
+            Part::Two => 2,
+        }
+    }
+}
+
+impl TryFrom<u8> for Part {
+    type Error = String;
+
+    fn try_from(part: u8) -> Result<Self, Self::Error> {
+        match part {
+            1 => Ok(Part::One),
+            2 => Ok(Part::Two),
+            _ => Err(format!("there is no part {part}")),
+        }
+    }
+}
+
+impl std::fmt::Display for Part {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Part::One => write!(f, "1"),
+            Part::Two => write!(f, "2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Integer(i128),
+    BigInteger(BigUint),
+    Text(String),
+    /// A rendered grid, one string per row.
+    Grid(Vec<String>),
+}
+
+impl std::fmt::Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Integer(n) => write!(f, "{n}"),
+            Answer::BigInteger(n) => write!(f, "{n}"),
+            Answer::Text(s) => write!(f, "{s}"),
+            Answer::Grid(rows) => write!(f, "{}", rows.join("\n")),
+        }
+    }
+}
+
+macro_rules! answer_from_int {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for Answer {
+                fn from(n: $t) -> Self {
+                    Answer::Integer(n.into())
+                }
+            }
+        )*
+    };
+}
+
+answer_from_int!(u8, u16, u32, u64, i8, i16, i32, i64, i128);
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Integer(n as i128)
+    }
+}
+
+impl From<BigUint> for Answer {
+    fn from(n: BigUint) -> Self {
+        Answer::BigInteger(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(s: String) -> Self {
+        Answer::Text(s)
+    }
+}
+
+impl From<&str> for Answer {
+    fn from(s: &str) -> Self {
+        Answer::Text(s.to_string())
+    }
+}
+
+/// 1-based position in a puzzle input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputLocation {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl InputLocation {
+    /// Locates `fragment`, which must be a subslice of `input`.
+    pub fn of(input: &str, fragment: &str) -> Option<Self> {
+        let outer = input.as_bytes().as_ptr_range();
+        let inner = fragment.as_bytes().as_ptr_range();
+        if inner.start < outer.start || inner.end > outer.end {
+            return None;
+        }
+
+        let offset = inner.start as usize - outer.start as usize;
+        if !input.is_char_boundary(offset) {
+            return None;
+        }
+
+        let before = &input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = offset - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+
+        Some(Self { line, column })
+    }
+}
+
+impl std::fmt::Display for InputLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveError {
+    pub day: Option<u8>,
+    pub part: Option<Part>,
+    pub location: Option<InputLocation>,
+    pub message: String,
+}
+
+impl SolveError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            day: None,
+            part: None,
+            location: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn at(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            location: Some(InputLocation { line, column }),
+            ..Self::new(message)
+        }
+    }
+
+    /// Error pointing at `fragment`, a subslice of `input`.
+    pub fn at_fragment(input: &str, fragment: &str, message: impl Into<String>) -> Self {
+        Self {
+            location: InputLocation::of(input, fragment),
+            ..Self::new(message)
+        }
+    }
+
+    pub fn with_day(mut self, day: u8) -> Self {
+        self.day.get_or_insert(day);
+        self
+    }
+
+    pub fn with_part(mut self, part: Part) -> Self {
+        self.part.get_or_insert(part);
+        self
+    }
+
+    fn from_panic(payload: Box<dyn std::any::Any + Send>) -> Self {
+        let message = if let Some(s) = payload.downcast_ref::<&str>() {
+            s.to_string()
+        } else if let Some(s) = payload.downcast_ref::<String>() {
+            s.clone()
+        } else {
+            String::from("panicked")
+        };
+
+        Self::new(message)
+    }
+}
+
+impl std::fmt::Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(day) = self.day {
+            write!(f, "day {day} ")?;
+        }
+        if let Some(part) = self.part {
+            write!(f, "part {part} ")?;
+        }
+        if let Some(location) = self.location {
+            write!(f, "at {location} ")?;
+        }
+        if self.day.is_some() || self.part.is_some() || self.location.is_some() {
+            write!(f, "failed: ")?;
+        }
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for SolveError {}
+
+impl From<input::InputError> for SolveError {
+    fn from(err: input::InputError) -> Self {
+        let day = match &err {
+            input::InputError::Missing { day, .. } | input::InputError::Empty { day, .. } => {
+                Some(*day)
+            }
+            input::InputError::Io { .. } => None,
+        };
+
+        Self {
+            day,
+            ..Self::new(err.to_string())
+        }
+    }
+}
+
+pub mod answers;
+pub mod baseline;
+pub mod cycle;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -42,8 +407,17 @@ pub mod day8;
 pub mod day9;
 pub mod direction;
 pub mod grid;
+pub mod input;
+pub mod instrument;
+pub mod invariant;
+pub mod linear;
+pub mod params;
+pub mod pathfinding;
+pub mod region;
+pub mod replay;
+pub mod runner;
 
-pub fn get_solution(day: u8) -> Box<dyn Solution> {
+pub fn get_solution(day: u8) -> Box<dyn DynSolution> {
     match day {
         1 => Box::new(day1::Day1::new()),
         2 => Box::new(day2::Day2::new()),
@@ -74,19 +448,13 @@ pub fn get_solution(day: u8) -> Box<dyn Solution> {
     }
 }
 
-pub fn get_input(day: u8) -> String {
-    // File path is in inputs/{}_input.txt
-    std::fs::read_to_string(format!("inputs/{}_input.txt", day)).unwrap()
+pub fn get_input(day: u8) -> Result<String, input::InputError> {
+    input::EnvInputProvider::new().get(day, input::DEFAULT_INPUT)
 }
 
-pub fn panic_after(count: usize) {
-    static mut COUNT: usize = 0;
-    unsafe {
-        COUNT += 1;
-        if COUNT >= count {
-            panic!("Panic after {} iterations", count);
-        }
-    }
+/// Known answers stored next to the inputs `get_input` reads.
+pub fn get_answers() -> Result<answers::AnswerRegistry, answers::AnswerError> {
+    input::EnvInputProvider::new().answers()
 }
 
 #[cfg(test)]
@@ -96,31 +464,82 @@ mod tests {
     #[ignore]
     #[test]
     fn test_all_parts() {
+        let answers = get_answers().unwrap();
+
         for day in 1..=25 {
             let mut solution = get_solution(day);
-            let input = get_input(day);
-            let input = input.trim();
-
-            let part1 = solution.part1(input);
-            dbg!(day, &part1, "done");
-            let part2 = solution.part2(input);
-            dbg!(day, &part2, "done");
-
-            if let Some(known_solution_part1) = solution.known_solution_part1() {
-                assert_eq!(
-                    known_solution_part1, part1,
-                    "Part 1 of day {} is wrong",
-                    day
-                );
+            let input = match get_input(day) {
+                Ok(input) => input,
+                Err(err) => {
+                    eprintln!("skipping: {err}");
+                    continue;
+                }
+            };
+            let parsed = solution
+                .parse_any(input.trim())
+                .map_err(|err| err.with_day(day))
+                .unwrap();
+
+            for &part in Part::all() {
+                let answer = solution
+                    .run_parsed(part, parsed.as_ref())
+                    .map_err(|err| err.with_day(day))
+                    .unwrap()
+                    .to_string();
+                dbg!(day, part, &answer, "done");
+
+                if let Some(known) = answers.get(day, input::DEFAULT_INPUT, part) {
+                    assert_eq!(known, answer, "Part {} of day {} is wrong", part, day);
+                }
             }
+        }
+    }
+
+    #[test]
+    fn test_input_location() {
+        let input = "1   2\n3   x\n";
+        let fragment = &input[10..11];
 
-            if let Some(known_solution_part2) = solution.known_solution_part2() {
-                assert_eq!(
-                    known_solution_part2, part2,
-                    "Part 2 of day {} is wrong",
-                    day
-                );
+        assert_eq!(
+            InputLocation::of(input, fragment),
+            Some(InputLocation { line: 2, column: 5 })
+        );
+        let elsewhere = String::from("x");
+        assert_eq!(InputLocation::of(input, &elsewhere), None);
+
+        // runs past the end of the input
+        let longer = "ab\ncd";
+        assert_eq!(InputLocation::of(&longer[..3], &longer[2..5]), None);
+        assert_eq!(
+            InputLocation::of(input, &input[input.len()..]),
+            Some(InputLocation { line: 3, column: 1 })
+        );
+    }
+
+    #[test]
+    fn test_panics_become_errors() {
+        struct Panics;
+
+        impl Solution for Panics {
+            fn new() -> Self {
+                Self
+            }
+
+            fn part1(&mut self, input: &str) -> String {
+                input.parse::<u32>().unwrap().to_string()
+            }
+
+            fn part2(&mut self, _: &str) -> String {
+                unreachable!()
             }
         }
+
+        let mut solution: Box<dyn DynSolution> = Box::new(Panics::new());
+        assert_eq!(solution.run(Part::One, "12"), Ok(Answer::from("12")));
+
+        let err = solution.run(Part::One, "x").unwrap_err().with_day(3);
+        assert_eq!(err.day, Some(3));
+        assert_eq!(err.part, Some(Part::One));
+        assert!(err.message.contains("InvalidDigit"));
     }
 }
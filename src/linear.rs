@@ -0,0 +1,242 @@
+//! Exact linear algebra over the integers for small systems.
+//!
+//! Everything works on `i128` and [`Rational`]s, so answers never depend on
+//! how close a float lands to an integer.
+
+pub fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `(g, x, y)` with `a * x + b * y == g`, `g` being the non-negative gcd.
+pub fn extended_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut old_r, mut r) = (a, b);
+    let (mut old_x, mut x) = (1, 0);
+    let (mut old_y, mut y) = (0, 1);
+
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_x, x) = (x, old_x - q * x);
+        (old_y, y) = (y, old_y - q * y);
+    }
+
+    if old_r < 0 {
+        (-old_r, -old_x, -old_y)
+    } else {
+        (old_r, old_x, old_y)
+    }
+}
+
+pub fn div_floor(a: i128, b: i128) -> i128 {
+    let q = a / b;
+    if a % b != 0 && (a < 0) != (b < 0) {
+        q - 1
+    } else {
+        q
+    }
+}
+
+pub fn div_ceil(a: i128, b: i128) -> i128 {
+    -div_floor(-a, b)
+}
+
+/// A fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i128,
+    den: i128,
+}
+
+impl Rational {
+    /// Panics if `den` is zero.
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "{num}/0 is not a number");
+
+        let g = gcd(num, den) * den.signum();
+        Self {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.num
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.den
+    }
+
+    /// The value if it is a whole number.
+    pub fn to_integer(&self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+}
+
+impl From<i128> for Rational {
+    fn from(n: i128) -> Self {
+        Self { num: n, den: 1 }
+    }
+}
+
+impl std::fmt::Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{den}", self.num),
+        }
+    }
+}
+
+/// A 2x2 matrix by rows.
+pub type Matrix2 = [[i128; 2]; 2];
+
+pub fn determinant(m: &Matrix2) -> i128 {
+    m[0][0] * m[1][1] - m[0][1] * m[1][0]
+}
+
+/// Solves `m * x = v` by Cramer's rule, or `None` when `m` is singular.
+pub fn cramer(m: &Matrix2, v: [i128; 2]) -> Option<[Rational; 2]> {
+    let det = determinant(m);
+    if det == 0 {
+        return None;
+    }
+
+    let x = determinant(&[[v[0], m[0][1]], [v[1], m[1][1]]]);
+    let y = determinant(&[[m[0][0], v[0]], [m[1][0], v[1]]]);
+
+    Some([Rational::new(x, det), Rational::new(y, det)])
+}
+
+/// Every integer solution of `a * x + b * y = c`, one per integer `k`:
+/// `x = x0 + k * dx` and `y = y0 - k * dy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Diophantine {
+    pub x0: i128,
+    pub y0: i128,
+    pub dx: i128,
+    pub dy: i128,
+}
+
+impl Diophantine {
+    /// `None` when there is no integer solution, or when `a` and `b` are
+    /// both zero and every pair solves it.
+    pub fn solve(a: i128, b: i128, c: i128) -> Option<Self> {
+        let (g, x, y) = extended_gcd(a, b);
+        if g == 0 || c % g != 0 {
+            return None;
+        }
+
+        let scale = c / g;
+        Some(Self {
+            x0: x * scale,
+            y0: y * scale,
+            dx: b / g,
+            dy: a / g,
+        })
+    }
+
+    pub fn at(&self, k: i128) -> (i128, i128) {
+        (self.x0 + k * self.dx, self.y0 - k * self.dy)
+    }
+
+    /// The range of `k` for which both `x` and `y` lie in `0..=max`, or
+    /// without an upper limit when `max` is `None`. The bounds are `None`
+    /// where `k` is unbounded.
+    pub fn non_negative(&self, max: Option<i128>) -> Option<(Option<i128>, Option<i128>)> {
+        let mut lo = None::<i128>;
+        let mut hi = None::<i128>;
+
+        // `v0 + k * step` has to stay within `0..=max`
+        for (v0, step) in [(self.x0, self.dx), (self.y0, -self.dy)] {
+            let (from, to) = match step {
+                0 if v0 < 0 || max.is_some_and(|max| v0 > max) => return None,
+                0 => continue,
+                step if step > 0 => (
+                    Some(div_ceil(-v0, step)),
+                    max.map(|max| div_floor(max - v0, step)),
+                ),
+                step => (
+                    max.map(|max| div_ceil(max - v0, step)),
+                    Some(div_floor(-v0, step)),
+                ),
+            };
+
+            lo = lo.max(from);
+            hi = match (hi, to) {
+                (Some(hi), Some(to)) => Some(hi.min(to)),
+                (hi, to) => hi.or(to),
+            };
+        }
+
+        match (lo, hi) {
+            (Some(lo), Some(hi)) if lo > hi => None,
+            bounds => Some(bounds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(gcd(0, 0), 0);
+
+        for (a, b) in [(240, 46), (-7, 3), (0, 5), (5, 0), (17, -17)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g, "{a} {b}");
+        }
+
+        assert_eq!(div_floor(-7, 2), -4);
+        assert_eq!(div_ceil(-7, 2), -3);
+        assert_eq!(div_floor(7, -2), -4);
+        assert_eq!(div_ceil(6, 3), 2);
+    }
+
+    #[test]
+    fn test_cramer() {
+        let m = [[94, 22], [34, 67]];
+        assert_eq!(
+            cramer(&m, [8400, 5400]),
+            Some([Rational::from(80), Rational::from(40)])
+        );
+
+        let [x, _] = cramer(&[[2, 0], [0, 1]], [3, 1]).unwrap();
+        assert_eq!((x.numerator(), x.denominator()), (3, 2));
+        assert_eq!(x.to_integer(), None);
+        assert_eq!(Rational::new(4, -6).to_string(), "-2/3");
+
+        assert_eq!(cramer(&[[1, 2], [2, 4]], [3, 6]), None);
+    }
+
+    #[test]
+    fn test_diophantine() {
+        let line = Diophantine::solve(4, 6, 20).unwrap();
+        for k in -3..3 {
+            let (x, y) = line.at(k);
+            assert_eq!(4 * x + 6 * y, 20);
+        }
+
+        // (5, 0), (2, 2)
+        let (lo, hi) = line.non_negative(None).unwrap();
+        let (lo, hi) = (lo.unwrap(), hi.unwrap());
+        let mut solutions = (lo..=hi).map(|k| line.at(k)).collect::<Vec<_>>();
+        solutions.sort();
+        assert_eq!(solutions, [(2, 2), (5, 0)]);
+
+        assert_eq!(
+            line.non_negative(Some(4)).map(|(lo, hi)| lo == hi),
+            Some(true)
+        );
+        assert_eq!(Diophantine::solve(4, 6, 21), None);
+        assert_eq!(Diophantine::solve(0, 0, 0), None);
+    }
+}
@@ -1,31 +1,447 @@
-use aoc2024::{get_input, get_solution};
-use clap::Parser;
+use std::path::PathBuf;
+
+use aoc2024::{
+    Part, TrySolution,
+    answers::AnswerRegistry,
+    baseline::{Baseline, Comparison, measure_day},
+    day15::{Day15, Frame},
+    get_solution,
+    input::{DEFAULT_INPUT, EnvInputProvider, InputProvider},
+    params::{self, parse_assignment},
+    replay::replay,
+    runner::{DayReport, DaySelection, Runner, Status},
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use itertools::Itertools;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// What day of the AoC is this?
-    num: u8,
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    selection: Selection,
+
+    /// How to print the results
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Fail a part once an iteration counter goes over this, e.g.
+    /// `day15.moves=1000`
+    #[arg(long, value_parser = parse_budget)]
+    budget: Vec<(String, u64)>,
+}
+
+fn parse_budget(s: &str) -> Result<(String, u64), String> {
+    let (counter, budget) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected COUNTER=N, got {s:?}"))?;
+    let budget = budget
+        .parse()
+        .map_err(|_| format!("{budget:?} is not a number"))?;
+
+    Ok((counter.to_string(), budget))
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Time every stage of each day and compare against a stored baseline
+    Bench(BenchArgs),
+    /// Step through the day 15 warehouse robot's moves
+    Replay(ReplayArgs),
+}
+
+#[derive(Args)]
+struct Selection {
+    /// Which days to run: `all`, `7`, `1..=15` or `3,7,11`
+    #[arg(required = true)]
+    days: Option<DaySelection>,
+
+    /// Only run this part
+    #[arg(short, long)]
+    part: Option<PartArg>,
+
+    /// Name of the input to run against (`inputs/{day}_{name}.txt`)
+    #[arg(short, long, default_value = DEFAULT_INPUT)]
+    input: String,
+
+    /// Set a parameter, e.g. `wide=11` for every selected day that has it or
+    /// `14.wide=11` for day 14 only; overrides the ones the known answers
+    /// give for the input
+    #[arg(long = "param", value_parser = parse_assignment)]
+    params: Vec<(String, String)>,
+}
+
+impl Selection {
+    fn days(&self) -> &[u8] {
+        self.days.as_ref().map_or(&[], |days| &days.0)
+    }
+
+    fn parts(&self) -> Vec<Part> {
+        match self.part {
+            Some(part) => vec![part.into()],
+            None => Part::all().to_vec(),
+        }
+    }
+}
+
+#[derive(Args)]
+struct BenchArgs {
+    #[command(flatten)]
+    selection: Selection,
+
+    /// How many times to run each stage
+    #[arg(short = 'n', long, default_value_t = 20)]
+    runs: usize,
+
+    /// Baseline file to compare against, or to save to with `--save`
+    #[arg(short, long, default_value = "baseline.json")]
+    baseline: PathBuf,
+
+    /// Record these timings in the baseline instead of comparing
+    #[arg(long)]
+    save: bool,
+
+    /// How many percent slower a median may get before it is a regression
+    #[arg(short, long, default_value_t = 10.0)]
+    threshold: f64,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// Which part's warehouse to replay
+    #[arg(short, long, default_value = "1")]
+    part: PartArg,
+
+    /// Name of the input to run against (`inputs/15_{name}.txt`)
+    #[arg(short, long, default_value = DEFAULT_INPUT)]
+    input: String,
+
+    /// Move to start at
+    #[arg(short, long, default_value_t = 0)]
+    start: usize,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// Summary table
+    Text,
+    /// One JSON array of records
+    Json,
+    /// One JSON record per line
+    Jsonl,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PartArg {
+    #[value(name = "1")]
+    One,
+    #[value(name = "2")]
+    Two,
+}
+
+impl From<PartArg> for Part {
+    fn from(part: PartArg) -> Self {
+        match part {
+            PartArg::One => Part::One,
+            PartArg::Two => Part::Two,
+        }
+    }
+}
+
+const ANSWER_WIDTH: usize = 20;
+
+fn truncate(s: &str, width: usize) -> String {
+    let first_line = s.lines().next().unwrap_or_default();
+
+    if first_line.chars().count() > width || first_line.len() != s.len() {
+        format!(
+            "{}…",
+            first_line.chars().take(width - 1).collect::<String>()
+        )
+    } else {
+        first_line.to_string()
+    }
+}
+
+fn print_table(reports: &[DayReport]) {
+    println!(
+        "{:>3}  {:>4}  {:<ANSWER_WIDTH$}  {:<7}  {:>10}  {:>10}  {:>10}",
+        "Day", "Part", "Answer", "Status", "Load", "Parse", "Time"
+    );
+
+    for report in reports {
+        for (i, part) in report.parts.iter().enumerate() {
+            let answer = match &part.answer {
+                Ok(answer) => answer.to_string(),
+                Err(err) => err.message.clone(),
+            };
+
+            let status = part.status();
+            let status_text = format!("{:<7}", status.to_string());
+            let status_text = match status {
+                Status::Pass => status_text.green(),
+                Status::Fail | Status::Error => status_text.red(),
+                Status::Unknown => status_text.yellow(),
+            };
+
+            let (load, parse) = if i == 0 {
+                (
+                    format!("{:.2?}", report.load),
+                    format!("{:.2?}", report.parse),
+                )
+            } else {
+                (String::new(), String::new())
+            };
+
+            println!(
+                "{:>3}  {:>4}  {:<ANSWER_WIDTH$}  {}  {:>10}  {:>10}  {:>10}",
+                report.day,
+                part.part.to_string(),
+                truncate(&answer, ANSWER_WIDTH),
+                status_text,
+                load,
+                parse,
+                format!("{:.2?}", part.duration),
+            );
+        }
+    }
+
+    let failures = reports
+        .iter()
+        .flat_map(|report| {
+            report
+                .parts
+                .iter()
+                .filter_map(|part| match (&part.answer, &part.expected) {
+                    (Err(err), _) => Some(err.to_string()),
+                    (Ok(answer), Some(expected)) if part.status() == Status::Fail => Some(format!(
+                        "day {} part {}: expected {expected}, got {answer}",
+                        report.day, part.part
+                    )),
+                    _ => None,
+                })
+        })
+        .collect::<Vec<_>>();
+
+    let iterations = reports
+        .iter()
+        .flat_map(|report| {
+            report.parts.iter().flat_map(move |part| {
+                part.counters.iter().map(move |(counter, count)| {
+                    format!("day {} part {}: {counter} {count}", report.day, part.part)
+                })
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for report in reports {
+        for part in &report.parts {
+            if let Some(rows) = &part.render {
+                println!("\nDay {} part {}", report.day, part.part);
+                for row in rows {
+                    println!("  {row}");
+                }
+            }
+        }
+    }
+
+    if !iterations.is_empty() {
+        println!("\nIterations per part");
+        for line in iterations {
+            println!("  {line}");
+        }
+    }
+
+    if !failures.is_empty() {
+        println!();
+        for failure in failures {
+            println!("{}", failure.red());
+        }
+    }
+}
+
+fn format_ns(ns: u64) -> String {
+    format!("{:.2?}", std::time::Duration::from_nanos(ns))
+}
+
+fn print_comparisons(comparisons: &[Comparison]) {
+    println!(
+        "{:>3}  {:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {:>8}",
+        "Day", "Stage", "Median", "p90", "p99", "Baseline", "Change"
+    );
+
+    for comparison in comparisons {
+        let timing = &comparison.timing;
+
+        let change = match comparison.change {
+            Some(change) => format!("{change:>+7.1}%"),
+            None => format!("{:>8}", "new"),
+        };
+        let change = if comparison.regressed {
+            change.red()
+        } else {
+            change.normal()
+        };
+
+        println!(
+            "{:>3}  {:<6}  {:>10}  {:>10}  {:>10}  {:>10}  {}",
+            timing.day,
+            timing.stage.to_string(),
+            format_ns(timing.median_ns),
+            format_ns(timing.p90_ns),
+            format_ns(timing.p99_ns),
+            comparison.baseline_ns.map(format_ns).unwrap_or_default(),
+            change,
+        );
+    }
+}
+
+fn bench(args: BenchArgs) {
+    let provider = EnvInputProvider::new();
+    let answers = load_answers(&provider);
+    let selection = &args.selection;
+
+    let mut current = Baseline::default();
+    for &day in selection.days() {
+        let overrides = params::for_day(day, selection.days(), &selection.params, |day| {
+            get_solution(day).param_names()
+        });
+        let overrides = match overrides {
+            Ok(overrides) => overrides,
+            Err(err) => {
+                eprintln!("{}", format!("day {day}: {err}").red());
+                continue;
+            }
+        };
+        let params = answers
+            .params(day, &selection.input)
+            .iter()
+            .cloned()
+            .chain(overrides)
+            .collect_vec();
+
+        match measure_day(
+            &provider,
+            day,
+            &selection.input,
+            &selection.parts(),
+            &params,
+            args.runs,
+        ) {
+            Ok(timings) => current.timings.extend(timings),
+            Err(err) => eprintln!("{}", err.to_string().red()),
+        }
+    }
+
+    let mut baseline = match Baseline::load(&args.baseline) {
+        Ok(baseline) => baseline,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Baseline::default(),
+        Err(err) => {
+            eprintln!("reading {}: {err}", args.baseline.display());
+            std::process::exit(1);
+        }
+    };
+
+    let comparisons = baseline.compare(&current, args.threshold);
+    print_comparisons(&comparisons);
+
+    if args.save {
+        baseline.merge(current);
+        if let Err(err) = baseline.save(&args.baseline) {
+            eprintln!("writing {}: {err}", args.baseline.display());
+            std::process::exit(1);
+        }
+        println!("\nSaved baseline to {}", args.baseline.display());
+    } else if comparisons.iter().any(|comparison| comparison.regressed) {
+        println!(
+            "\n{}",
+            format!("Some stages got more than {}% slower", args.threshold).red()
+        );
+        std::process::exit(1);
+    }
+}
+
+fn load_answers(provider: &impl InputProvider) -> AnswerRegistry {
+    match provider.answers() {
+        Ok(answers) => answers,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn replay_day15(args: ReplayArgs) {
+    let warehouse = EnvInputProvider::new()
+        .get(15, &args.input)
+        .map_err(|err| err.to_string())
+        .and_then(|input| Day15::parse(input.trim()).map_err(|err| err.to_string()));
+    let warehouse = match warehouse {
+        Ok(warehouse) => warehouse,
+        Err(err) => {
+            eprintln!("{}", err.red());
+            std::process::exit(1);
+        }
+    };
+
+    let mut frames: Vec<Frame> = vec![];
+    let simulated = match Part::from(args.part) {
+        Part::One => warehouse.simulate(&mut frames),
+        Part::Two => warehouse.simulate_wide(&mut frames),
+    };
+    if let Err(err) = simulated {
+        eprintln!("{}", err.to_string().red());
+    }
+
+    if let Err(err) = replay(&frames, args.start) {
+        eprintln!("{err}");
+        std::process::exit(1);
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
 
-    let input = get_input(cli.num);
-    let input = input.trim();
-    let mut solution = get_solution(cli.num);
-
-    let part1 = solution.part1(input);
-    if let Some(known_part1) = solution.known_solution_part1() {
-        assert_eq!(part1, known_part1);
+    match cli.command {
+        Some(Command::Bench(args)) => return bench(args),
+        Some(Command::Replay(args)) => return replay_day15(args),
+        None => {}
     }
 
-    println!("Part 1: {}", part1);
+    let provider = EnvInputProvider::new();
+    let answers = load_answers(&provider);
 
-    let part2 = solution.part2(input);
-    if let Some(known_part2) = solution.known_solution_part2() {
-        assert_eq!(part2, known_part2);
+    let runner = Runner::new(provider, answers)
+        .input(&cli.selection.input)
+        .parts(&cli.selection.parts());
+    let runner = cli.budget.iter().fold(runner, |runner, (counter, budget)| {
+        runner.budget(counter, *budget)
+    });
+    let runner = cli
+        .selection
+        .params
+        .iter()
+        .fold(runner, |runner, (name, value)| runner.param(name, value));
+    let reports = runner.run(cli.selection.days());
+
+    match cli.format {
+        Format::Text => print_table(&reports),
+        Format::Json => {
+            let records = reports.iter().flat_map(DayReport::records).collect_vec();
+            println!("{}", serde_json::to_string_pretty(&records).unwrap());
+        }
+        Format::Jsonl => {
+            for record in reports.iter().flat_map(DayReport::records) {
+                println!("{}", serde_json::to_string(&record).unwrap());
+            }
+        }
     }
 
-    println!("Part 2: {}", part2);
+    if !reports.iter().all(DayReport::is_ok) {
+        std::process::exit(1);
+    }
 }
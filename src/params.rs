@@ -0,0 +1,312 @@
+//! Typed knobs for days whose examples differ from the real input in more
+//! than the input text, like the size of the floor on day 14.
+//!
+//! A day declares its parameters with [`params!`](crate::params!) and picks
+//! them up as [`TrySolution::Params`](crate::TrySolution::Params). The runner
+//! sets them by name from strings, taken from the known answers of an input
+//! and from `--param name=value` on the command line, see [`for_day`].
+
+/// A set of named parameters with defaults.
+pub trait Params: Clone + Default {
+    /// Names of all parameters, in declaration order.
+    const NAMES: &'static [&'static str];
+
+    /// Sets the parameter `name` from its textual form.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), ParamError>;
+}
+
+/// Days without parameters.
+impl Params for () {
+    const NAMES: &'static [&'static str] = &[];
+
+    fn set(&mut self, name: &str, _: &str) -> Result<(), ParamError> {
+        Err(ParamError::Unknown {
+            name: name.to_string(),
+            known: Self::NAMES,
+        })
+    }
+}
+
+/// Declares a parameter struct with a default for every field:
+///
+/// ```
+/// aoc2024::params! {
+///     pub struct FloorParams {
+///         /// Width of the floor.
+///         wide: i64 = 101,
+///         tall: i64 = 103,
+///     }
+/// }
+///
+/// use aoc2024::params::Params;
+///
+/// let mut params = FloorParams::default();
+/// params.set("wide", "11").unwrap();
+/// assert_eq!((params.wide, params.tall), (11, 103));
+/// ```
+///
+/// Every field type has to implement [`FromStr`](std::str::FromStr).
+#[macro_export]
+macro_rules! params {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                $field:ident: $ty:ty = $default:expr
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq)]
+        $vis struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl Default for $name {
+            fn default() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+        }
+
+        impl $crate::params::Params for $name {
+            const NAMES: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn set(&mut self, name: &str, value: &str) -> Result<(), $crate::params::ParamError> {
+                match name {
+                    $(
+                        stringify!($field) => {
+                            self.$field = value.parse().map_err(|err| {
+                                $crate::params::ParamError::Invalid {
+                                    name: name.to_string(),
+                                    value: value.to_string(),
+                                    message: format!("{err}"),
+                                }
+                            })?;
+                        }
+                    )*
+                    _ => {
+                        return Err($crate::params::ParamError::Unknown {
+                            name: name.to_string(),
+                            known: <Self as $crate::params::Params>::NAMES,
+                        });
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    };
+}
+
+/// Splits `name=value`, as passed to `--param`.
+pub fn parse_assignment(s: &str) -> Result<(String, String), String> {
+    let (name, value) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected NAME=VALUE, got {s:?}"))?;
+
+    Ok((name.trim().to_string(), value.trim().to_string()))
+}
+
+/// The overrides out of `overrides` that go to `day` in a run of `days`,
+/// with `names` giving each day's parameters.
+///
+/// `14.wide=11` only goes to day 14, and is an error when day 14 is not one
+/// of `days`. A bare `wide=11` goes to every day with a `wide` parameter, and
+/// is an error only when none of `days` has one.
+pub fn for_day(
+    day: u8,
+    days: &[u8],
+    overrides: &[(String, String)],
+    names: impl Fn(u8) -> &'static [&'static str],
+) -> Result<Vec<(String, String)>, ParamError> {
+    let mut selected = vec![];
+
+    for (name, value) in overrides {
+        match name.split_once('.') {
+            Some((scope, name)) if scope.parse() == Ok(day) => {
+                selected.push((name.to_string(), value.clone()));
+            }
+            Some((scope, _)) if let Ok(scope) = scope.parse::<u8>() => {
+                if !days.contains(&scope) {
+                    return Err(ParamError::NotInRun {
+                        name: name.clone(),
+                        day: scope,
+                    });
+                }
+            }
+            _ if names(day).contains(&name.as_str()) => {
+                selected.push((name.clone(), value.clone()));
+            }
+            _ if days
+                .iter()
+                .any(|&other| names(other).contains(&name.as_str())) => {}
+            _ if days == [day] => {
+                return Err(ParamError::Unknown {
+                    name: name.clone(),
+                    known: names(day),
+                });
+            }
+            _ => return Err(ParamError::Unclaimed { name: name.clone() }),
+        }
+    }
+
+    Ok(selected)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParamError {
+    Unknown {
+        name: String,
+        known: &'static [&'static str],
+    },
+    /// No day of a run has the parameter.
+    Unclaimed { name: String },
+    /// The parameter is scoped to a day the run leaves out.
+    NotInRun { name: String, day: u8 },
+    Invalid {
+        name: String,
+        value: String,
+        message: String,
+    },
+}
+
+impl std::fmt::Display for ParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamError::Unknown { name, known: [] } => {
+                write!(f, "no parameter {name:?}, this day takes none")
+            }
+            ParamError::Unknown { name, known } => {
+                write!(
+                    f,
+                    "no parameter {name:?}, expected one of {}",
+                    known.join(", ")
+                )
+            }
+            ParamError::Unclaimed { name } => {
+                write!(f, "none of the selected days has a parameter {name:?}")
+            }
+            ParamError::NotInRun { name, day } => {
+                write!(f, "{name:?} is for day {day}, which is not selected")
+            }
+            ParamError::Invalid {
+                name,
+                value,
+                message,
+            } => write!(f, "{value:?} is not a valid {name}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+impl From<ParamError> for crate::SolveError {
+    fn from(err: ParamError) -> Self {
+        crate::SolveError::new(err.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    crate::params! {
+        struct TestParams {
+            size: usize = 71,
+            bytes: usize = 1024,
+        }
+    }
+
+    #[test]
+    fn test_set() {
+        let mut params = TestParams::default();
+        params.set("size", "7").unwrap();
+        assert_eq!(
+            params,
+            TestParams {
+                size: 7,
+                bytes: 1024
+            }
+        );
+
+        assert_eq!(
+            params.set("sizes", "7").unwrap_err().to_string(),
+            r#"no parameter "sizes", expected one of size, bytes"#
+        );
+        assert!(matches!(
+            params.set("bytes", "-1"),
+            Err(ParamError::Invalid { .. })
+        ));
+        assert!(().set("size", "7").is_err());
+
+        assert_eq!(
+            parse_assignment("wide = 11"),
+            Ok(("wide".to_string(), "11".to_string()))
+        );
+        assert!(parse_assignment("wide").is_err());
+    }
+
+    #[test]
+    fn test_for_day() {
+        let names = |day: u8| -> &'static [&'static str] {
+            match day {
+                14 => &["wide", "tall"],
+                18 => &["size", "bytes"],
+                _ => &[],
+            }
+        };
+        let overrides = |list: &[(&str, &str)]| {
+            list.iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let given = overrides(&[("wide", "11"), ("18.size", "7"), ("14.tall", "7")]);
+        assert_eq!(
+            for_day(14, &[1, 14, 18], &given, names),
+            Ok(overrides(&[("wide", "11"), ("tall", "7")]))
+        );
+        assert_eq!(
+            for_day(18, &[1, 14, 18], &given, names),
+            Ok(overrides(&[("size", "7")]))
+        );
+        assert_eq!(for_day(1, &[1, 14, 18], &given, names), Ok(vec![]));
+
+        // a scoped name is left for the day to reject
+        assert_eq!(
+            for_day(1, &[1], &overrides(&[("1.wide", "11")]), names),
+            Ok(overrides(&[("wide", "11")]))
+        );
+
+        let typo = overrides(&[("high", "7")]);
+        assert_eq!(
+            for_day(1, &[1, 14], &typo, names).unwrap_err().to_string(),
+            r#"none of the selected days has a parameter "high""#
+        );
+
+        let elsewhere = overrides(&[("99.wide", "11")]);
+        assert_eq!(
+            for_day(14, &[1, 14], &elsewhere, names),
+            Err(ParamError::NotInRun {
+                name: "99.wide".to_string(),
+                day: 99,
+            })
+        );
+        assert_eq!(
+            for_day(1, &[1, 14], &elsewhere, names)
+                .unwrap_err()
+                .to_string(),
+            r#""99.wide" is for day 99, which is not selected"#
+        );
+        assert!(matches!(
+            for_day(14, &[14], &typo, names),
+            Err(ParamError::Unknown { .. })
+        ));
+    }
+}
@@ -0,0 +1,376 @@
+//! Shortest paths over any state space, grid cells or otherwise.
+//!
+//! Every search takes a start state and a `successors` function, so the same
+//! code walks plain [`Coord`]s, `(Coord, QuadDirection)` for mazes where
+//! turning costs extra, or anything else that is `Copy + Eq + Hash`.
+
+use std::{cmp::Ordering, collections::BinaryHeap, collections::VecDeque, hash::Hash};
+
+use ahash::{AHashMap, AHashSet};
+
+use crate::grid::{Coord, Grid};
+
+/// Number of steps from `start` to every state reachable from it.
+pub fn bfs<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> AHashMap<S, usize>
+where
+    S: Copy + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = AHashMap::new();
+    distances.insert(start, 0);
+
+    let mut queue = VecDeque::from([start]);
+    while let Some(state) = queue.pop_front() {
+        let distance = distances[&state];
+
+        for next in successors(&state) {
+            if !distances.contains_key(&next) {
+                distances.insert(next, distance + 1);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    distances
+}
+
+/// [`bfs`] over the orthogonal neighbors of a grid, only stepping onto cells
+/// that are `passable`. Unreachable cells are `None`.
+pub fn bfs_grid<T>(
+    grid: &Grid<T>,
+    start: Coord,
+    passable: impl Fn(Coord, &T) -> bool,
+) -> Grid<Option<usize>> {
+    let mut distances = Grid::new_blank(grid.width, grid.height, None);
+
+    let reachable = bfs(start, |&coord| {
+        grid.neighbors4(coord)
+            .filter(|&(neighbor, t)| passable(neighbor, t))
+            .map(|(neighbor, _)| neighbor)
+            .collect::<Vec<_>>()
+    });
+
+    for (coord, distance) in reachable {
+        distances[coord] = Some(distance);
+    }
+
+    distances
+}
+
+/// Entry of the priority queue, ordered so the cheapest pops first.
+struct Queued<S> {
+    cost: u64,
+    priority: u64,
+    state: S,
+}
+
+impl<S> PartialEq for Queued<S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+    }
+}
+
+impl<S> Eq for Queued<S> {}
+
+impl<S> PartialOrd for Queued<S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S> Ord for Queued<S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.priority.cmp(&self.priority)
+    }
+}
+
+/// Result of [`dijkstra`]: the cheapest cost of every reachable state and,
+/// for each, every state it can be reached from at that cost.
+#[derive(Debug, Clone)]
+pub struct ShortestPaths<S> {
+    pub distances: AHashMap<S, u64>,
+    /// The "all shortest paths" DAG, pointing backwards towards the start.
+    pub predecessors: AHashMap<S, Vec<S>>,
+}
+
+impl<S: Copy + Eq + Hash> ShortestPaths<S> {
+    pub fn distance(&self, state: &S) -> Option<u64> {
+        self.distances.get(state).copied()
+    }
+
+    /// One cheapest path from the start to `goal`, both included.
+    pub fn path_to(&self, goal: S) -> Option<Vec<S>> {
+        self.distances.get(&goal)?;
+
+        let mut path = vec![goal];
+        while let Some(&previous) = self
+            .predecessors
+            .get(path.last().unwrap())
+            .and_then(|previous| previous.first())
+        {
+            path.push(previous);
+        }
+        path.reverse();
+
+        Some(path)
+    }
+
+    /// Every state on any cheapest path to any of `goals`.
+    ///
+    /// Pass all goal states that share the cheapest cost, e.g. every facing of
+    /// the end tile.
+    pub fn on_any_path(&self, goals: impl IntoIterator<Item = S>) -> AHashSet<S> {
+        let mut seen = AHashSet::new();
+        let mut stack = goals
+            .into_iter()
+            .filter(|goal| self.distances.contains_key(goal))
+            .collect::<Vec<_>>();
+
+        while let Some(state) = stack.pop() {
+            if !seen.insert(state) {
+                continue;
+            }
+
+            stack.extend(self.predecessors.get(&state).into_iter().flatten());
+        }
+
+        seen
+    }
+
+    /// How many distinct cheapest paths lead to `goal`.
+    pub fn count_paths(&self, goal: S) -> u64 {
+        fn count<S: Copy + Eq + Hash>(
+            paths: &ShortestPaths<S>,
+            state: S,
+            memo: &mut AHashMap<S, u64>,
+        ) -> u64 {
+            if let Some(&n) = memo.get(&state) {
+                return n;
+            }
+
+            let n = match paths.predecessors.get(&state) {
+                Some(previous) => previous.iter().map(|&p| count(paths, p, memo)).sum(),
+                None => 1,
+            };
+            memo.insert(state, n);
+
+            n
+        }
+
+        if !self.distances.contains_key(&goal) {
+            return 0;
+        }
+
+        count(self, goal, &mut AHashMap::new())
+    }
+}
+
+/// Cheapest cost from `start` to every reachable state, where `successors`
+/// yields each next state with the cost of moving there. Costs have to be
+/// positive for the predecessors to form a DAG.
+pub fn dijkstra<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> ShortestPaths<S>
+where
+    S: Copy + Eq + Hash,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    let mut distances = AHashMap::new();
+    let mut predecessors: AHashMap<S, Vec<S>> = AHashMap::new();
+    distances.insert(start, 0);
+
+    let mut queue = BinaryHeap::from([Queued {
+        cost: 0,
+        priority: 0,
+        state: start,
+    }]);
+
+    while let Some(Queued { cost, state, .. }) = queue.pop() {
+        if cost > distances[&state] {
+            continue;
+        }
+
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+
+            match distances.get(&next) {
+                Some(&best) if next_cost > best => {}
+                Some(&best) if next_cost == best => {
+                    predecessors.entry(next).or_default().push(state);
+                }
+                _ => {
+                    distances.insert(next, next_cost);
+                    predecessors.insert(next, vec![state]);
+                    queue.push(Queued {
+                        cost: next_cost,
+                        priority: next_cost,
+                        state: next,
+                    });
+                }
+            }
+        }
+    }
+
+    ShortestPaths {
+        distances,
+        predecessors,
+    }
+}
+
+/// Cheapest path from `start` to the first state that `is_goal`, guided by a
+/// `heuristic` that must never overestimate the remaining cost.
+pub fn astar<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    heuristic: impl Fn(&S) -> u64,
+    is_goal: impl Fn(&S) -> bool,
+) -> Option<(u64, Vec<S>)>
+where
+    S: Copy + Eq + Hash,
+    I: IntoIterator<Item = (S, u64)>,
+{
+    let mut distances = AHashMap::new();
+    let mut came_from = AHashMap::new();
+    distances.insert(start, 0);
+
+    let mut queue = BinaryHeap::from([Queued {
+        cost: 0,
+        priority: heuristic(&start),
+        state: start,
+    }]);
+
+    while let Some(Queued { cost, state, .. }) = queue.pop() {
+        if is_goal(&state) {
+            let mut path = vec![state];
+            while let Some(&previous) = came_from.get(path.last().unwrap()) {
+                path.push(previous);
+            }
+            path.reverse();
+
+            return Some((cost, path));
+        }
+
+        if cost > distances[&state] {
+            continue;
+        }
+
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+
+            if distances.get(&next).is_none_or(|&best| next_cost < best) {
+                distances.insert(next, next_cost);
+                came_from.insert(next, state);
+                queue.push(Queued {
+                    cost: next_cost,
+                    priority: next_cost + heuristic(&next),
+                    state: next,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Heuristic for [`astar`] on grids where every step costs at least one.
+pub fn manhattan_to(goal: Coord) -> impl Fn(&Coord) -> u64 {
+    move |coord| coord.manhattan_distance(goal) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::direction::QuadDirection;
+
+    const MAZE: &str = "###############
+#.......#....E#
+#.#.###.#.###.#
+#.....#.#...#.#
+#.###.#####.#.#
+#.#.#.......#.#
+#.#.#####.###.#
+#...........#.#
+###.#.#####.#.#
+#...#.....#.#.#
+#.#.#.###.#.#.#
+#.....#...#.#.#
+#.###.#.#.#.#.#
+#S..#.....#...#
+###############";
+
+    fn find(grid: &Grid<u8>, c: u8) -> Coord {
+        grid.iter_with_coords()
+            .find_map(|(coord, &v)| (v == c).then_some(coord))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bfs_grid() {
+        let grid = Grid::new("S.#\n..#\n#..", |c| c);
+        let distances = bfs_grid(&grid, Coord::new(0, 0), |_, &c| c != b'#');
+
+        assert_eq!(distances[Coord::new(2, 2)], Some(4));
+        assert_eq!(distances[Coord::new(0, 2)], None);
+    }
+
+    #[test]
+    fn test_dijkstra_with_turns() {
+        let grid = Grid::new(MAZE, |c| c);
+        let start = (find(&grid, b'S'), QuadDirection::Right);
+        let end = find(&grid, b'E');
+
+        let paths = dijkstra(start, |&(coord, dir)| {
+            let mut next = vec![
+                ((coord, dir.rotate_left()), 1000),
+                ((coord, dir.rotate_right()), 1000),
+            ];
+            if grid.get(coord + dir).is_some_and(|&c| c != b'#') {
+                next.push(((coord + dir, dir), 1));
+            }
+            next
+        });
+
+        let ends = QuadDirection::get_all_directions()
+            .iter()
+            .map(|&dir| (end, dir))
+            .collect::<Vec<_>>();
+        let best = ends.iter().filter_map(|end| paths.distance(end)).min();
+        assert_eq!(best, Some(7036));
+
+        let best_ends = ends
+            .into_iter()
+            .filter(|end| paths.distance(end) == best)
+            .collect::<Vec<_>>();
+        let tiles = paths
+            .on_any_path(best_ends.iter().copied())
+            .into_iter()
+            .map(|(coord, _)| coord)
+            .collect::<AHashSet<_>>();
+        assert_eq!(tiles.len(), 45);
+
+        let path = paths.path_to(best_ends[0]).unwrap();
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&best_ends[0]));
+        assert!(paths.count_paths(best_ends[0]) >= 2);
+    }
+
+    #[test]
+    fn test_astar() {
+        let grid = Grid::new(MAZE, |c| c);
+        let start = find(&grid, b'S');
+        let end = find(&grid, b'E');
+
+        let successors = |&coord: &Coord| {
+            grid.neighbors4(coord)
+                .filter(|(_, c)| **c != b'#')
+                .map(|(neighbor, _)| (neighbor, 1))
+                .collect::<Vec<_>>()
+        };
+
+        let (cost, path) = astar(start, successors, manhattan_to(end), |&c| c == end).unwrap();
+        let distances = bfs_grid(&grid, start, |_, &c| c != b'#');
+
+        assert_eq!(Some(cost as usize), distances[end]);
+        assert_eq!(path.len() as u64, cost + 1);
+        assert!(path.windows(2).all(|w| w[0].is_adjacent(w[1])));
+        assert_eq!(astar(start, successors, manhattan_to(end), |_| false), None);
+    }
+}
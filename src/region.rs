@@ -0,0 +1,154 @@
+//! Connected regions of a [`Grid`] and their shapes.
+
+use crate::{
+    direction::QuadDirection,
+    grid::{Coord, Grid},
+};
+
+/// One orthogonally connected region of cells.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    pub id: usize,
+    pub area: usize,
+    /// Number of cell edges that border another region or the outside.
+    pub perimeter: usize,
+    /// Number of straight sides of the outline, holes included.
+    pub sides: usize,
+    /// Top left corner of the bounding box.
+    pub min: Coord,
+    /// Bottom right corner of the bounding box, inclusive.
+    pub max: Coord,
+    pub cells: Vec<Coord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Regions {
+    /// Which region every cell belongs to, indexing into `regions`.
+    pub ids: Grid<usize>,
+    pub regions: Vec<Region>,
+}
+
+impl<T> Grid<T> {
+    /// Splits the grid into regions of orthogonal neighbors for which `eq`
+    /// holds, numbered in reading order of their first cell.
+    pub fn label_regions(&self, eq: impl Fn(&T, &T) -> bool) -> Regions {
+        let mut ids = Grid::new_blank(self.width, self.height, usize::MAX);
+        let mut cells_per_region = vec![];
+
+        for (coord, _) in self.iter_with_coords() {
+            if ids[coord] != usize::MAX {
+                continue;
+            }
+
+            let id = cells_per_region.len();
+            ids[coord] = id;
+
+            let mut cells = vec![coord];
+            let mut stack = vec![coord];
+            while let Some(current) = stack.pop() {
+                for (neighbor, value) in self.neighbors4(current) {
+                    if ids[neighbor] == usize::MAX && eq(&self[current], value) {
+                        ids[neighbor] = id;
+                        cells.push(neighbor);
+                        stack.push(neighbor);
+                    }
+                }
+            }
+
+            cells_per_region.push(cells);
+        }
+
+        let regions = cells_per_region
+            .into_iter()
+            .enumerate()
+            .map(|(id, cells)| Region::measure(&ids, id, cells))
+            .collect();
+
+        Regions { ids, regions }
+    }
+}
+
+impl Region {
+    fn measure(ids: &Grid<usize>, id: usize, mut cells: Vec<Coord>) -> Self {
+        cells.sort();
+
+        let same = |coord: Coord| ids.get(coord) == Some(&id);
+
+        let mut perimeter = 0;
+        let mut sides = 0;
+        for &cell in &cells {
+            perimeter += QuadDirection::get_all_directions()
+                .iter()
+                .filter(|&&dir| !same(cell + dir))
+                .count();
+
+            // every side of an outline ends in exactly one corner
+            for &dir in QuadDirection::get_all_directions() {
+                let next = dir.rotate_right();
+                let (a, b) = (same(cell + dir), same(cell + next));
+                let diagonal = same(cell + dir + next);
+
+                if (!a && !b) || (a && b && !diagonal) {
+                    sides += 1;
+                }
+            }
+        }
+
+        let (min_row, max_row) = cells
+            .iter()
+            .map(Coord::row)
+            .fold((i16::MAX, i16::MIN), |(lo, hi), r| (lo.min(r), hi.max(r)));
+        let (min_col, max_col) = cells
+            .iter()
+            .map(Coord::col)
+            .fold((i16::MAX, i16::MIN), |(lo, hi), c| (lo.min(c), hi.max(c)));
+
+        Self {
+            id,
+            area: cells.len(),
+            perimeter,
+            sides,
+            min: Coord::new(min_row, min_col),
+            max: Coord::new(max_row, max_col),
+            cells,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_regions() {
+        let grid = Grid::new("AAAA\nBBCD\nBBCC\nEEEC", |c| c);
+        let regions = grid.label_regions(|a, b| a == b);
+
+        assert_eq!(regions.regions.len(), 5);
+        assert_eq!(regions.ids[Coord::new(3, 3)], regions.ids[Coord::new(1, 2)]);
+
+        let c = &regions.regions[regions.ids[Coord::new(1, 2)]];
+        assert_eq!((c.area, c.perimeter, c.sides), (4, 10, 8));
+        assert_eq!((c.min, c.max), (Coord::new(1, 2), Coord::new(3, 3)));
+
+        let a = &regions.regions[0];
+        assert_eq!((a.area, a.perimeter, a.sides), (4, 10, 4));
+        assert_eq!(a.cells[0], Coord::new(0, 0));
+    }
+
+    #[test]
+    fn test_sides_with_holes() {
+        let grid = Grid::new("AAAAAA\nAAABBA\nAAABBA\nABBAAA\nABBAAA\nAAAAAA", |c| c);
+        let regions = grid.label_regions(|a, b| a == b);
+
+        assert_eq!(regions.regions[0].sides, 12);
+        assert_eq!(
+            regions
+                .regions
+                .iter()
+                .map(|r| r.area * r.sides)
+                .sum::<usize>(),
+            368
+        );
+    }
+}
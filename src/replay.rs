@@ -0,0 +1,203 @@
+//! Terminal replay of recorded simulation frames.
+//!
+//! `→`/`l`/space steps forward, `←`/`h` steps back, page up and down move by
+//! a hundred frames, home and end go to either end, `g` followed by a frame
+//! number and enter jumps there and `q` or escape quits.
+
+use std::io::Write;
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{self, ClearType},
+};
+
+/// Something [`replay`] can draw.
+pub trait ReplayFrame {
+    fn title(&self) -> String;
+    fn rows(&self) -> Vec<String>;
+}
+
+/// Where a replay is and what the keys pressed so far asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay {
+    pub position: usize,
+    pub len: usize,
+    /// Digits typed after `g`, waiting for enter.
+    pub jump: Option<String>,
+    pub quit: bool,
+}
+
+impl Replay {
+    pub fn new(len: usize, position: usize) -> Self {
+        Self {
+            position: position.min(len.saturating_sub(1)),
+            len,
+            jump: None,
+            quit: false,
+        }
+    }
+
+    fn go_to(&mut self, position: usize) {
+        self.position = position.min(self.len.saturating_sub(1));
+    }
+
+    pub fn handle(&mut self, key: KeyCode) {
+        if let Some(jump) = &mut self.jump {
+            match key {
+                KeyCode::Char(c) if c.is_ascii_digit() => jump.push(c),
+                KeyCode::Backspace => {
+                    jump.pop();
+                }
+                KeyCode::Enter => {
+                    if let Ok(position) = jump.parse() {
+                        self.go_to(position);
+                    }
+                    self.jump = None;
+                }
+                KeyCode::Esc => self.jump = None,
+                _ => {}
+            }
+
+            return;
+        }
+
+        match key {
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char(' ') => {
+                self.go_to(self.position + 1)
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.go_to(self.position.saturating_sub(1)),
+            KeyCode::PageDown => self.go_to(self.position + 100),
+            KeyCode::PageUp => self.go_to(self.position.saturating_sub(100)),
+            KeyCode::Home => self.go_to(0),
+            KeyCode::End => self.go_to(self.len),
+            KeyCode::Char('g') => self.jump = Some(String::new()),
+            KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+            _ => {}
+        }
+    }
+}
+
+/// Puts the terminal back the way it was, even when drawing fails.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> std::io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(
+            std::io::stdout(),
+            terminal::EnterAlternateScreen,
+            cursor::Hide
+        )?;
+
+        Ok(Self)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(
+            std::io::stdout(),
+            cursor::Show,
+            terminal::LeaveAlternateScreen
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(frames: &[impl ReplayFrame], replay: &Replay) -> std::io::Result<()> {
+    let mut stdout = std::io::stdout();
+    let frame = &frames[replay.position];
+
+    queue!(
+        stdout,
+        terminal::Clear(ClearType::All),
+        cursor::MoveTo(0, 0),
+        Print(format!(
+            "frame {}/{}: {}\r\n\r\n",
+            replay.position,
+            replay.len - 1,
+            frame.title()
+        ))
+    )?;
+
+    for row in frame.rows() {
+        queue!(stdout, Print(row), Print("\r\n"))?;
+    }
+
+    let footer = match &replay.jump {
+        Some(jump) => format!("\r\njump to frame: {jump}"),
+        None => "\r\n←/→ step  PgUp/PgDn ±100  Home/End  g jump  q quit".to_string(),
+    };
+    queue!(stdout, Print(footer))?;
+
+    stdout.flush()
+}
+
+/// Steps through `frames` in the terminal, starting at frame `start`.
+pub fn replay(frames: &[impl ReplayFrame], start: usize) -> std::io::Result<()> {
+    if frames.is_empty() {
+        return Ok(());
+    }
+
+    let _terminal = RawTerminal::enter()?;
+    let mut replay = Replay::new(frames.len(), start);
+
+    while !replay.quit {
+        draw(frames, &replay)?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+                break;
+            }
+
+            replay.handle(key.code);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keys() {
+        let mut replay = Replay::new(500, 0);
+
+        replay.handle(KeyCode::Left);
+        assert_eq!(replay.position, 0);
+
+        replay.handle(KeyCode::Right);
+        replay.handle(KeyCode::Char(' '));
+        assert_eq!(replay.position, 2);
+
+        replay.handle(KeyCode::PageDown);
+        assert_eq!(replay.position, 102);
+
+        replay.handle(KeyCode::End);
+        assert_eq!(replay.position, 499);
+
+        for key in [
+            KeyCode::Char('g'),
+            KeyCode::Char('4'),
+            KeyCode::Char('2'),
+            KeyCode::Char('q'),
+            KeyCode::Enter,
+        ] {
+            replay.handle(key);
+        }
+        assert_eq!(replay.position, 42);
+        assert!(!replay.quit);
+
+        replay.handle(KeyCode::Char('q'));
+        assert!(replay.quit);
+    }
+}
@@ -0,0 +1,544 @@
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Answer, DynSolution, Part, SolveError,
+    answers::AnswerRegistry,
+    get_solution,
+    input::{DEFAULT_INPUT, InputProvider},
+    instrument, params,
+};
+
+pub const DAYS: std::ops::RangeInclusive<u8> = 1..=25;
+
+/// Days picked on the command line: `all`, `7`, `1..=15`, `1..15`, `3,7,11`
+/// or any comma separated mix of those.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DaySelection(pub Vec<u8>);
+
+impl std::str::FromStr for DaySelection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse_day = |s: &str| -> Result<u8, String> {
+            let day = s
+                .trim()
+                .parse::<u8>()
+                .map_err(|_| format!("{s:?} is not a day"))?;
+
+            if !DAYS.contains(&day) {
+                return Err(format!(
+                    "day {day} is outside {}..={}",
+                    DAYS.start(),
+                    DAYS.end()
+                ));
+            }
+
+            Ok(day)
+        };
+
+        let mut days = vec![];
+
+        for item in s.split(',') {
+            let item = item.trim();
+
+            if item == "all" {
+                days.extend(DAYS);
+            } else if let Some((from, to)) = item.split_once("..=") {
+                days.extend(parse_day(from)?..=parse_day(to)?);
+            } else if let Some((from, to)) = item.split_once("..") {
+                days.extend(parse_day(from)?..parse_day(to)?);
+            } else {
+                days.push(parse_day(item)?);
+            }
+        }
+
+        days.sort();
+        days.dedup();
+
+        if days.is_empty() {
+            return Err(format!("{s:?} selects no days"));
+        }
+
+        Ok(Self(days))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Fail,
+    Unknown,
+    Error,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Pass => write!(f, "pass"),
+            Status::Fail => write!(f, "fail"),
+            Status::Unknown => write!(f, "unknown"),
+            Status::Error => write!(f, "error"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PartReport {
+    pub part: Part,
+    pub answer: Result<Answer, SolveError>,
+    pub expected: Option<String>,
+    pub duration: Duration,
+    /// How far each [`instrument`] counter went up while solving.
+    pub counters: BTreeMap<String, u64>,
+    /// See [`TrySolution::take_render`](crate::TrySolution::take_render).
+    pub render: Option<Vec<String>>,
+}
+
+impl PartReport {
+    pub fn status(&self) -> Status {
+        match (&self.answer, &self.expected) {
+            (Err(_), _) => Status::Error,
+            (Ok(_), None) => Status::Unknown,
+            (Ok(answer), Some(expected)) if answer.to_string() == *expected => Status::Pass,
+            (Ok(_), Some(_)) => Status::Fail,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct DayReport {
+    pub day: u8,
+    pub input: String,
+    /// Time spent fetching the input.
+    pub load: Duration,
+    /// Time spent in [`TrySolution::parse`](crate::TrySolution::parse).
+    pub parse: Duration,
+    /// When the input could not be loaded or parsed every part carries that
+    /// error.
+    pub parts: Vec<PartReport>,
+}
+
+impl DayReport {
+    pub fn is_ok(&self) -> bool {
+        self.parts
+            .iter()
+            .all(|part| matches!(part.status(), Status::Pass | Status::Unknown))
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = RunRecord> + '_ {
+        self.parts.iter().map(|part| RunRecord {
+            day: self.day,
+            part: part.part,
+            input: self.input.clone(),
+            answer: part.answer.as_ref().ok().map(Answer::to_string),
+            expected: part.expected.clone(),
+            status: part.status(),
+            duration_ns: part.duration.as_nanos() as u64,
+            parse_ns: self.parse.as_nanos() as u64,
+            error: part.answer.as_ref().err().map(SolveError::to_string),
+            counters: part.counters.clone(),
+            render: part.render.clone(),
+        })
+    }
+}
+
+/// One line of the runner's machine readable output.
+///
+/// This is the schema behind `--format json` and `--format jsonl`; fields are
+/// only ever added, never renamed or removed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub day: u8,
+    pub part: Part,
+    pub input: String,
+    pub answer: Option<String>,
+    pub expected: Option<String>,
+    pub status: Status,
+    pub duration_ns: u64,
+    pub error: Option<String>,
+    /// Parse time of the whole day, shared by both parts.
+    pub parse_ns: u64,
+    /// Iterations per [`instrument`] counter.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub counters: BTreeMap<String, u64>,
+    /// Rows of the picture the part drew, if it drew one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub render: Option<Vec<String>>,
+}
+
+pub struct Runner<P> {
+    provider: P,
+    answers: AnswerRegistry,
+    input: String,
+    parts: Vec<Part>,
+    budgets: Vec<(String, u64)>,
+    params: Vec<(String, String)>,
+    solutions: BTreeMap<u8, Box<dyn Fn() -> Box<dyn DynSolution>>>,
+}
+
+impl<P: InputProvider> Runner<P> {
+    pub fn new(provider: P, answers: AnswerRegistry) -> Self {
+        Self {
+            provider,
+            answers,
+            input: DEFAULT_INPUT.to_string(),
+            parts: Part::all().to_vec(),
+            budgets: vec![],
+            params: vec![],
+            solutions: BTreeMap::new(),
+        }
+    }
+
+    pub fn input(mut self, name: &str) -> Self {
+        self.input = name.to_string();
+        self
+    }
+
+    pub fn parts(mut self, parts: &[Part]) -> Self {
+        self.parts = parts.to_vec();
+        self
+    }
+
+    /// Solves `day` with whatever `solution` returns instead of
+    /// [`get_solution`], e.g. a solution set up for an example input.
+    pub fn solution(
+        mut self,
+        day: u8,
+        solution: impl Fn() -> Box<dyn DynSolution> + 'static,
+    ) -> Self {
+        self.solutions.insert(day, Box::new(solution));
+        self
+    }
+
+    /// Sets parameter `name`, on top of the ones the known answers give for
+    /// the input; `14.wide` only for day 14, a bare `wide` for every day run
+    /// that has it, see [`params::for_day`].
+    pub fn param(mut self, name: &str, value: &str) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Fails every part that ticks `counter` more than `budget` times.
+    pub fn budget(mut self, counter: &str, budget: u64) -> Self {
+        self.budgets.push((counter.to_string(), budget));
+        self
+    }
+
+    fn solution_for(&self, day: u8) -> Box<dyn DynSolution> {
+        match self.solutions.get(&day) {
+            Some(solution) => solution(),
+            None => get_solution(day),
+        }
+    }
+
+    pub fn run_day(&self, day: u8) -> DayReport {
+        self.run_day_of(day, &[day])
+    }
+
+    /// Runs `day` as one of `days`, which decides where bare `--param`
+    /// names go.
+    fn run_day_of(&self, day: u8, days: &[u8]) -> DayReport {
+        let start = Instant::now();
+        let input = self.provider.get(day, &self.input);
+        let load = start.elapsed();
+
+        let mut solution = self.solution_for(day);
+
+        let configured = params::for_day(day, days, &self.params, |day| {
+            self.solution_for(day).param_names()
+        })
+        .map_err(SolveError::from)
+        .and_then(|overrides| {
+            let params = self
+                .answers
+                .params(day, &self.input)
+                .iter()
+                .cloned()
+                .chain(overrides)
+                .collect::<Vec<_>>();
+
+            if params.is_empty() {
+                Ok(())
+            } else {
+                solution.configure(&params)
+            }
+        });
+
+        let start = Instant::now();
+        let parsed = configured
+            .and(input.map_err(SolveError::from))
+            .and_then(|input| solution.parse_any(input.trim()))
+            .map_err(|err| err.with_day(day));
+        let parse = start.elapsed();
+
+        let parts = self
+            .parts
+            .iter()
+            .map(|&part| {
+                let expected = self.answers.get(day, &self.input, part).map(str::to_string);
+
+                let parsed = match &parsed {
+                    Ok(parsed) => parsed,
+                    Err(err) => {
+                        return PartReport {
+                            part,
+                            answer: Err(err.clone().with_part(part)),
+                            expected,
+                            duration: Duration::ZERO,
+                            counters: BTreeMap::new(),
+                            render: None,
+                        };
+                    }
+                };
+
+                let context = instrument::Context::new();
+                for (counter, budget) in &self.budgets {
+                    context.set_budget(counter, *budget);
+                }
+
+                let start = Instant::now();
+                let answer = context
+                    .enter(|| solution.run_parsed(part, parsed.as_ref()))
+                    .map_err(|err| err.with_day(day));
+                let duration = start.elapsed();
+
+                PartReport {
+                    part,
+                    answer,
+                    expected,
+                    duration,
+                    counters: context.snapshot(),
+                    render: solution.take_render(),
+                }
+            })
+            .collect();
+
+        DayReport {
+            day,
+            input: self.input.clone(),
+            load,
+            parse,
+            parts,
+        }
+    }
+
+    pub fn run(&self, days: &[u8]) -> Vec<DayReport> {
+        days.iter().map(|&day| self.run_day_of(day, days)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::MemoryInputProvider;
+
+    #[test]
+    fn test_day_selection() {
+        let parse = |s: &str| s.parse::<DaySelection>().map(|d| d.0);
+
+        assert_eq!(parse("all").unwrap(), DAYS.collect::<Vec<_>>());
+        assert_eq!(parse("1..=3").unwrap(), vec![1, 2, 3]);
+        assert_eq!(parse("1..3").unwrap(), vec![1, 2]);
+        assert_eq!(parse("11,3,7,3").unwrap(), vec![3, 7, 11]);
+        assert_eq!(parse("1..=2, 9").unwrap(), vec![1, 2, 9]);
+        assert!(parse("0").is_err());
+        assert!(parse("26").is_err());
+        assert!(parse("3..1").is_err());
+        assert!(parse("x").is_err());
+    }
+
+    #[test]
+    fn test_runner() {
+        let provider = MemoryInputProvider::new()
+            .with(1, "example", "3   4\n4   3\n2   5\n1   3\n3   9\n3   3")
+            .with(2, "example", "7 6 4 2 1\n1 2 7 8 9");
+        let mut answers = AnswerRegistry::new();
+        answers.insert(1, "example", Part::One, "11");
+        answers.insert(1, "example", Part::Two, "32");
+
+        let reports = Runner::new(provider, answers)
+            .input("example")
+            .run(&[1, 2, 3]);
+
+        let statuses = |report: &DayReport| {
+            report
+                .parts
+                .iter()
+                .map(PartReport::status)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(statuses(&reports[0]), vec![Status::Pass, Status::Fail]);
+        assert_eq!(
+            statuses(&reports[1]),
+            vec![Status::Unknown, Status::Unknown]
+        );
+        assert_eq!(statuses(&reports[2]), vec![Status::Error, Status::Error]);
+        assert!(!reports[0].is_ok());
+        assert!(reports[1].is_ok());
+    }
+
+    #[test]
+    fn test_counters_and_budgets() {
+        let warehouse = "#####\n#@O.#\n#####\n\n>>>";
+        let provider = MemoryInputProvider::new().with(15, "example", warehouse);
+
+        let report = Runner::new(provider, AnswerRegistry::new())
+            .input("example")
+            .parts(&[Part::One])
+            .run_day(15);
+        assert_eq!(report.parts[0].counters["day15.moves"], 3);
+
+        let provider = MemoryInputProvider::new().with(15, "example", warehouse);
+        let report = Runner::new(provider, AnswerRegistry::new())
+            .input("example")
+            .parts(&[Part::One])
+            .budget("day15.moves", 2)
+            .run_day(15);
+        let err = report.parts[0].answer.as_ref().unwrap_err();
+        assert!(
+            err.message
+                .contains("day15.moves went over its budget of 2")
+        );
+        assert!(err.message.contains("day15.rs"));
+    }
+
+    #[test]
+    fn test_solution_override() {
+        let robots = "p=0,4 v=3,-3\np=6,3 v=-1,-3\np=10,3 v=-1,2\np=2,0 v=2,-1\n\
+                      p=0,0 v=1,3\np=3,0 v=-2,-2\np=7,6 v=-1,-3\np=3,0 v=-1,-2\n\
+                      p=9,3 v=2,3\np=7,3 v=-1,2\np=2,4 v=2,-3\np=9,5 v=-3,-3";
+        let provider = MemoryInputProvider::new().with(14, "example", robots);
+        let mut answers = AnswerRegistry::new();
+        answers.insert(14, "example", Part::One, "12");
+
+        let report = Runner::new(provider, answers)
+            .input("example")
+            .parts(&[Part::One])
+            .solution(14, || {
+                Box::new(crate::day14::Day14::new_with_size(11, 7).unwrap())
+            })
+            .run_day(14);
+
+        assert_eq!(report.parts[0].status(), Status::Pass);
+        assert_eq!(report.parts[0].render, None);
+
+        // the picture rides next to the answer instead of replacing it
+        let report = Runner::new(
+            MemoryInputProvider::new().with(14, "example", robots),
+            AnswerRegistry::new(),
+        )
+        .input("example")
+        .param("render", "true")
+        .solution(14, || {
+            Box::new(crate::day14::Day14::new_with_size(11, 7).unwrap())
+        })
+        .run_day(14);
+
+        assert_eq!(report.parts[0].render, None);
+        assert!(matches!(report.parts[1].answer, Ok(Answer::Integer(_))));
+        assert_eq!(report.parts[1].render.as_ref().map(Vec::len), Some(7));
+        let records = report.records().collect::<Vec<_>>();
+        assert_eq!(records[1].render, report.parts[1].render);
+    }
+
+    #[test]
+    fn test_params() {
+        let robots = "p=0,4 v=3,-3\np=6,3 v=-1,-3\np=10,3 v=-1,2\np=2,0 v=2,-1\n\
+                      p=0,0 v=1,3\np=3,0 v=-2,-2\np=7,6 v=-1,-3\np=3,0 v=-1,-2\n\
+                      p=9,3 v=2,3\np=7,3 v=-1,2\np=2,4 v=2,-3\np=9,5 v=-3,-3";
+        let answers = AnswerRegistry::parse(
+            r#"
+[14.example]
+part1 = "12"
+params = { wide = 11, tall = 100 }
+"#,
+        )
+        .unwrap();
+
+        let runner = |answers: AnswerRegistry| {
+            Runner::new(
+                MemoryInputProvider::new().with(14, "example", robots),
+                answers,
+            )
+            .input("example")
+            .parts(&[Part::One])
+        };
+
+        // the command line wins over the registry
+        let report = runner(answers.clone()).param("tall", "7").run_day(14);
+        assert_eq!(report.parts[0].status(), Status::Pass);
+
+        let report = runner(answers.clone()).run_day(14);
+        assert_eq!(report.parts[0].status(), Status::Fail);
+
+        let report = runner(answers.clone()).param("high", "7").run_day(14);
+        let err = report.parts[0].answer.as_ref().unwrap_err();
+        assert_eq!(err.day, Some(14));
+        assert!(err.message.contains("no parameter \"high\""));
+
+        // days without the parameter are left alone
+        let runner = |answers: AnswerRegistry| {
+            Runner::new(
+                MemoryInputProvider::new().with(14, "example", robots).with(
+                    1,
+                    "example",
+                    "3   4\n4   3",
+                ),
+                answers,
+            )
+            .input("example")
+            .parts(&[Part::One])
+        };
+        let reports = runner(answers.clone()).param("tall", "7").run(&[1, 14]);
+        assert_eq!(reports[0].parts[0].status(), Status::Unknown);
+        assert_eq!(reports[1].parts[0].status(), Status::Pass);
+
+        let reports = runner(answers.clone()).param("14.tall", "7").run(&[1, 14]);
+        assert_eq!(reports[1].parts[0].status(), Status::Pass);
+
+        let reports = runner(answers.clone()).param("high", "7").run(&[1, 14]);
+        assert!(
+            reports
+                .iter()
+                .all(|report| report.parts[0].status() == Status::Error)
+        );
+
+        // a day outside the run cannot take a scoped parameter either
+        let reports = runner(answers).param("99.wide", "7").run(&[1, 14]);
+        assert!(
+            reports
+                .iter()
+                .all(|report| report.parts[0].status() == Status::Error)
+        );
+    }
+
+    #[test]
+    fn test_records_round_trip() {
+        let provider = MemoryInputProvider::new().with(1, "example", "3   4\n4   3");
+        let mut answers = AnswerRegistry::new();
+        answers.insert(1, "example", Part::One, "0");
+
+        let report = Runner::new(provider, answers).input("example").run_day(1);
+        let records = report.records().collect::<Vec<_>>();
+
+        assert_eq!(records[0].part, Part::One);
+        assert_eq!(records[0].answer.as_deref(), Some("0"));
+        assert_eq!(records[0].status, Status::Pass);
+        assert_eq!(records[1].expected, None);
+
+        let json = serde_json::to_string(&records[0]).unwrap();
+        assert!(json.contains(r#""part":1"#));
+        assert!(json.contains(r#""status":"pass""#));
+        assert_eq!(
+            serde_json::from_str::<RunRecord>(&json).unwrap(),
+            records[0]
+        );
+    }
+}